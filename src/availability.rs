@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use git2::{Oid, Time};
+use serde_json::Value;
+
+use crate::{video::read_json, History, Result, TimeSource, VideoInfo};
+
+/// Whether a video in the playlist can still be watched.
+///
+/// YouTube keeps private and deleted videos in playlists as placeholders,
+/// which the backup records in their song files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Availability {
+    Available,
+    Private,
+    Deleted,
+}
+
+/// A video that is unavailable at the tip of the history.
+#[derive(Clone, Debug)]
+pub struct UnavailableVideo {
+    pub video_id: String,
+    pub availability: Availability,
+    /// Details from the last version of the song file that was available.
+    pub last_known: VideoInfo,
+    /// When the video became unavailable, if it ever was available.
+    pub since: Option<Time>,
+}
+
+impl Availability {
+    pub fn of(json: &Value) -> Availability {
+        let title = json
+            .pointer("/snippet/title")
+            .or_else(|| json.get("title"))
+            .and_then(Value::as_str);
+        match title {
+            Some("Private video") => return Availability::Private,
+            Some("Deleted video") | None => return Availability::Deleted,
+            Some(_) => {}
+        }
+        match json
+            .pointer("/status/privacyStatus")
+            .and_then(Value::as_str)
+        {
+            Some("private") => Availability::Private,
+            _ => Availability::Available,
+        }
+    }
+
+    pub fn is_available(self) -> bool {
+        self == Availability::Available
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Availability::Available => "available",
+            Availability::Private => "private",
+            Availability::Deleted => "deleted",
+        }
+    }
+}
+
+impl History {
+    /// Finds the unavailable videos at `tip` along with what they were last
+    /// known as, walking back through history only as far as needed.
+    pub fn unavailable_videos(
+        &self,
+        tip: Oid,
+        time_source: TimeSource,
+    ) -> Result<Vec<UnavailableVideo>> {
+        let repo = self.repository();
+        let layout = self.layout();
+        let songs = |oid: Oid| -> Result<HashMap<String, Oid>> {
+            let tree = repo.find_commit(oid)?.tree()?;
+            let Ok(entry) = tree.get_path(&layout.songs_dir) else {
+                return Ok(HashMap::new());
+            };
+            let songs = entry.to_object(repo)?.peel_to_tree()?;
+            Ok(songs
+                .iter()
+                .filter_map(|entry| {
+                    let video = layout.video_id(&layout.songs_dir.join(entry.name()?))?;
+                    Some((video, entry.id()))
+                })
+                .collect())
+        };
+
+        let mut unavailable: Vec<UnavailableVideo> = Vec::new();
+        for (video_id, blob) in songs(tip)? {
+            let Some(json) = read_json(repo, blob) else {
+                continue;
+            };
+            let availability = Availability::of(&json);
+            if !availability.is_available() {
+                unavailable.push(UnavailableVideo {
+                    video_id,
+                    availability,
+                    last_known: VideoInfo::default(),
+                    since: None,
+                });
+            }
+        }
+        unavailable.sort_by(|a, b| a.video_id.cmp(&b.video_id));
+
+        // Walk back until every video has an available version or is gone.
+        let mut unresolved: Vec<usize> = (0..unavailable.len()).collect();
+        let mut newer_time: HashMap<usize, Time> = HashMap::new();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(tip)?;
+        for oid in revwalk {
+            if unresolved.is_empty() {
+                break;
+            }
+            let oid = oid?;
+            let time = time_source.time(&repo.find_commit(oid)?);
+            let songs = songs(oid)?;
+            unresolved.retain(|&i| {
+                let video = &mut unavailable[i];
+                let json = songs
+                    .get(&video.video_id)
+                    .and_then(|blob| read_json(repo, *blob));
+                let Some(json) = json else {
+                    return false;
+                };
+                if Availability::of(&json).is_available() {
+                    video.last_known = VideoInfo::from_json(&json);
+                    video.since = newer_time.get(&i).copied();
+                    return false;
+                }
+                newer_time.insert(i, time);
+                true
+            });
+        }
+        Ok(unavailable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn video(title: &str, privacy: &str) -> Value {
+        json!({
+            "snippet": { "title": title },
+            "status": { "privacyStatus": privacy },
+        })
+    }
+
+    #[test]
+    fn public_and_unlisted_videos_are_available() {
+        assert_eq!(
+            Availability::of(&video("Song", "public")),
+            Availability::Available
+        );
+        assert_eq!(
+            Availability::of(&video("Song", "unlisted")),
+            Availability::Available
+        );
+        assert_eq!(
+            Availability::of(&json!({ "title": "Song" })),
+            Availability::Available
+        );
+    }
+
+    #[test]
+    fn placeholder_titles() {
+        assert_eq!(
+            Availability::of(&video("Private video", "public")),
+            Availability::Private
+        );
+        assert_eq!(
+            Availability::of(&video("Deleted video", "public")),
+            Availability::Deleted
+        );
+        assert_eq!(
+            Availability::of(&json!({ "title": "Private video" })),
+            Availability::Private
+        );
+    }
+
+    #[test]
+    fn private_status() {
+        assert_eq!(
+            Availability::of(&video("Song", "private")),
+            Availability::Private
+        );
+    }
+
+    #[test]
+    fn missing_titles_count_as_deleted() {
+        let deleted = Availability::Deleted;
+        assert_eq!(Availability::of(&json!({})), deleted);
+        assert_eq!(Availability::of(&json!({ "snippet": {} })), deleted);
+        assert_eq!(
+            Availability::of(&json!({ "status": { "privacyStatus": "public" } })),
+            deleted
+        );
+        assert!(!deleted.is_available());
+    }
+}
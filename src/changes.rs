@@ -0,0 +1,137 @@
+use serde_json::Value;
+
+use crate::Selector;
+
+/// A watched field of a song file that differs between two commits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: Selector,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl FieldChange {
+    /// Compares the watched `fields` of two versions of a song file.
+    pub fn between(old: &Value, new: &Value, fields: &[Selector]) -> Vec<FieldChange> {
+        fields
+            .iter()
+            .filter_map(|field| {
+                let old = field.select(old).first().map(|value| (*value).clone());
+                let new = field.select(new).first().map(|value| (*value).clone());
+                (old != new).then(|| FieldChange {
+                    field: field.clone(),
+                    old,
+                    new,
+                })
+            })
+            .collect()
+    }
+
+    /// Describes the change in words, e.g. `Title changed from "A" to "B"`.
+    pub fn describe(&self) -> String {
+        let name = self.field.name().unwrap_or_default();
+        if name == "privacyStatus" {
+            return match &self.new {
+                Some(Value::String(status)) => format!("Became {}", status),
+                _ => "Privacy status removed".to_string(),
+            };
+        }
+        let label = label(name);
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => format!("{} changed from {} to {}", label, old, new),
+            (None, Some(new)) => format!("{} set to {}", label, new),
+            (Some(old), None) => format!("{} {} removed", label, old),
+            (None, None) => format!("{} unchanged", label),
+        }
+    }
+}
+
+/// Turns a camelCase key into a sentence-case label.
+fn label(key: &str) -> String {
+    let mut label = String::new();
+    for c in key.chars() {
+        if label.is_empty() {
+            label.extend(c.to_uppercase());
+        } else if c.is_uppercase() {
+            label.push(' ');
+            label.extend(c.to_lowercase());
+        } else {
+            label.push(c);
+        }
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn fields(fields: &[&str]) -> Vec<Selector> {
+        fields.iter().map(|field| field.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn finds_only_changed_fields() {
+        let old = json!({ "snippet": { "title": "A", "channelTitle": "C" } });
+        let new = json!({ "snippet": { "title": "B", "channelTitle": "C" } });
+        let changes = FieldChange::between(
+            &old,
+            &new,
+            &fields(&["snippet.title", "snippet.channelTitle", "snippet.missing"]),
+        );
+        assert_eq!(
+            changes,
+            [FieldChange {
+                field: "snippet.title".parse().unwrap(),
+                old: Some(json!("A")),
+                new: Some(json!("B")),
+            }]
+        );
+    }
+
+    #[test]
+    fn describes_changes() {
+        let old = json!({ "snippet": { "title": "A" } });
+        let new = json!({ "snippet": { "videoOwnerChannelTitle": "C" } });
+        let changes = FieldChange::between(
+            &old,
+            &new,
+            &fields(&["snippet.title", "snippet.videoOwnerChannelTitle"]),
+        );
+        let descriptions: Vec<String> = changes.iter().map(FieldChange::describe).collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Title \"A\" removed",
+                "Video owner channel title set to \"C\""
+            ]
+        );
+
+        let renamed = FieldChange::between(
+            &json!({ "title": "A" }),
+            &json!({ "title": "B" }),
+            &fields(&["title"]),
+        );
+        assert_eq!(renamed[0].describe(), "Title changed from \"A\" to \"B\"");
+    }
+
+    #[test]
+    fn describes_privacy_changes() {
+        let field = fields(&["status.privacyStatus"]);
+        let public = json!({ "status": { "privacyStatus": "public" } });
+        let private = json!({ "status": { "privacyStatus": "private" } });
+        let changes = FieldChange::between(&public, &private, &field);
+        assert_eq!(changes[0].describe(), "Became private");
+        let changes = FieldChange::between(&private, &json!({}), &field);
+        assert_eq!(changes[0].describe(), "Privacy status removed");
+    }
+
+    #[test]
+    fn labels_camel_case_keys() {
+        assert_eq!(label("videoOwnerChannelTitle"), "Video owner channel title");
+        assert_eq!(label("title"), "Title");
+        assert_eq!(label(""), "");
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use git2::{Oid, Time};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    output::Output,
+    report::{Format, ReportOptions},
+    Layout, Links, Options, Result, Selector, State, TimeSource,
+};
+
+/// Sidecar file recording how far an incremental report got.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// The last commit included in the report.
+    pub commit: Oid,
+    pub format: Format,
+    pub rules: Rules,
+    pub state: State,
+}
+
+/// The options that decide which events end up in a report. A report built
+/// with other rules is rebuilt rather than appended to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub include_initial: bool,
+    pub full_history: bool,
+    pub modifications: bool,
+    pub watch_fields: Vec<Selector>,
+    pub availability: bool,
+    /// The `--to` revision as given, so that a branch name keeps following
+    /// the branch.
+    pub to: Option<String>,
+    /// Where the events are read from.
+    pub layout: Layout,
+    pub time_source: TimeSource,
+    /// How the events are written, so that new sections look like the old.
+    pub presentation: ReportOptions,
+}
+
+impl Rules {
+    pub fn new(
+        options: &Options,
+        to: Option<&str>,
+        layout: &Layout,
+        presentation: &ReportOptions,
+    ) -> Rules {
+        Rules {
+            include_initial: options.include_initial,
+            full_history: options.full_history,
+            modifications: options.modifications,
+            watch_fields: options.watch_fields.clone(),
+            availability: options.availability,
+            to: to.map(str::to_string),
+            layout: layout.clone(),
+            time_source: options.time_source,
+            presentation: presentation.clone(),
+        }
+    }
+}
+
+impl Checkpoint {
+    /// Where the checkpoint for a report at `output` is kept.
+    pub fn path_for(output: &Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".state");
+        PathBuf::from(path)
+    }
+
+    /// Loads a checkpoint, returning `None` if there is none or it cannot be
+    /// understood, in which case the report has to be rebuilt.
+    pub fn load(path: &Path) -> Result<Option<Checkpoint>> {
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_slice(&content)
+            .ok()
+            .and_then(|json| Checkpoint::from_json(&json)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut output = Output::open(path, true)?;
+        serde_json::to_writer(&mut output, &self.to_json()).map_err(io::Error::from)?;
+        writeln!(output)?;
+        output.finish()?;
+        Ok(())
+    }
+
+    fn to_json(&self) -> Value {
+        let watch_fields: Vec<String> = self
+            .rules
+            .watch_fields
+            .iter()
+            .map(Selector::to_string)
+            .collect();
+        let layout = &self.rules.layout;
+        let presentation = &self.rules.presentation;
+        let mut already_added: Vec<&String> = self.state.already_added.iter().collect();
+        already_added.sort();
+        let removed_at: Map<String, Value> = self
+            .state
+            .removed_at
+            .iter()
+            .map(|(video, time)| {
+                let time = json!({
+                    "seconds": time.seconds(),
+                    "offset_minutes": time.offset_minutes(),
+                });
+                (video.clone(), time)
+            })
+            .collect();
+        json!({
+            "commit": self.commit.to_string(),
+            "format": self.format.to_possible_value().unwrap().get_name(),
+            "include_initial": self.rules.include_initial,
+            "full_history": self.rules.full_history,
+            "modifications": self.rules.modifications,
+            "watch_fields": watch_fields,
+            "availability": self.rules.availability,
+            "to": self.rules.to,
+            "songs_dir": layout.songs_dir.to_string_lossy(),
+            "summary_path": layout.summary_path.to_string_lossy(),
+            "song_extension": layout.song_extension,
+            "id_selector": layout.id_selector.as_ref().map(Selector::to_string),
+            "time_source": self.rules.time_source.to_possible_value().unwrap().get_name(),
+            "date_format": presentation.date_format.to_string(),
+            "timezone": presentation.timezone.to_string(),
+            "link_style": presentation.links.style.to_string(),
+            "playlist_id": presentation.links.playlist,
+            "already_added": already_added,
+            "removed_at": removed_at,
+        })
+    }
+
+    fn from_json(json: &Value) -> Option<Checkpoint> {
+        let commit = Oid::from_str(json.get("commit")?.as_str()?).ok()?;
+        let format = Format::from_str(json.get("format")?.as_str()?, false).ok()?;
+        let flag = |key| json.get(key)?.as_bool();
+        let string = |key| json.get(key)?.as_str();
+        let optional = |key| match json.get(key)? {
+            Value::Null => Some(None),
+            value => Some(Some(value.as_str()?.to_string())),
+        };
+        let rules = Rules {
+            include_initial: flag("include_initial")?,
+            full_history: flag("full_history")?,
+            modifications: flag("modifications")?,
+            watch_fields: json
+                .get("watch_fields")?
+                .as_array()?
+                .iter()
+                .map(|field| field.as_str()?.parse().ok())
+                .collect::<Option<_>>()?,
+            availability: flag("availability")?,
+            to: optional("to")?,
+            layout: Layout {
+                songs_dir: PathBuf::from(string("songs_dir")?),
+                summary_path: PathBuf::from(string("summary_path")?),
+                song_extension: string("song_extension")?.to_string(),
+                id_selector: match optional("id_selector")? {
+                    Some(selector) => Some(selector.parse().ok()?),
+                    None => None,
+                },
+            },
+            time_source: TimeSource::from_str(string("time_source")?, false).ok()?,
+            presentation: ReportOptions {
+                date_format: string("date_format")?.parse().ok()?,
+                timezone: string("timezone")?.parse().ok()?,
+                links: Links {
+                    style: string("link_style")?.parse().ok()?,
+                    playlist: optional("playlist_id")?,
+                },
+            },
+        };
+        let already_added = json
+            .get("already_added")?
+            .as_array()?
+            .iter()
+            .map(|video| video.as_str().map(str::to_string))
+            .collect::<Option<_>>()?;
+        let removed_at = json
+            .get("removed_at")?
+            .as_object()?
+            .iter()
+            .map(|(video, time)| {
+                let seconds = time.get("seconds")?.as_i64()?;
+                let offset = time.get("offset_minutes")?.as_i64()?;
+                Some((video.clone(), Time::new(seconds, offset as i32)))
+            })
+            .collect::<Option<_>>()?;
+        Some(Checkpoint {
+            commit,
+            format,
+            rules,
+            state: State {
+                already_added,
+                removed_at,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        let mut state = State::default();
+        state.already_added.insert("aaaaaaaaaaa".to_string());
+        state.already_added.insert("bbbbbbbbbbb".to_string());
+        state
+            .removed_at
+            .insert("bbbbbbbbbbb".to_string(), Time::new(1_677_677_400, -300));
+        Checkpoint {
+            commit: Oid::from_str("2b8bf6e1dfca32938525c836605cede710ffc55e").unwrap(),
+            format: Format::Jsonl,
+            rules: Rules {
+                full_history: true,
+                modifications: true,
+                watch_fields: vec!["snippet.title".parse().unwrap()],
+                to: Some("main".to_string()),
+                layout: Layout {
+                    songs_dir: PathBuf::from("songs"),
+                    id_selector: Some("items[].id".parse().unwrap()),
+                    ..Layout::default()
+                },
+                time_source: TimeSource::Author,
+                presentation: ReportOptions {
+                    date_format: "%Y-%m-%d".parse().unwrap(),
+                    timezone: "Europe/Berlin".parse().unwrap(),
+                    links: Links {
+                        style: "invidious:yewtu.be".parse().unwrap(),
+                        playlist: Some("PL123".to_string()),
+                    },
+                },
+                ..Rules::default()
+            },
+            state,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let checkpoint = checkpoint();
+        let loaded = Checkpoint::from_json(&checkpoint.to_json()).unwrap();
+        assert_eq!(loaded.commit, checkpoint.commit);
+        assert_eq!(loaded.format, checkpoint.format);
+        assert_eq!(loaded.rules, checkpoint.rules);
+        assert_eq!(loaded.state.already_added, checkpoint.state.already_added);
+        assert_eq!(loaded.state.removed_at, checkpoint.state.removed_at);
+    }
+
+    #[test]
+    fn round_trips_default_rules() {
+        let checkpoint = Checkpoint {
+            rules: Rules::default(),
+            state: State::default(),
+            ..checkpoint()
+        };
+        let loaded = Checkpoint::from_json(&checkpoint.to_json()).unwrap();
+        assert_eq!(loaded.rules, Rules::default());
+        assert!(loaded.state.already_added.is_empty());
+    }
+
+    #[test]
+    fn rejects_incomplete_checkpoints() {
+        let mut json = checkpoint().to_json();
+        json.as_object_mut().unwrap().remove("full_history");
+        assert!(Checkpoint::from_json(&json).is_none());
+        assert!(Checkpoint::from_json(&Value::Null).is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use git2::{Delta, DiffOptions, Oid, Time};
+
+use crate::{History, Result, SnapshotEntry, TimeSource, VideoInfo};
+
+/// The net change to the playlist between two commits, ignoring whatever
+/// was added and removed again in between.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// `None` when comparing from before the first commit.
+    pub from: Option<Oid>,
+    pub from_time: Option<Time>,
+    pub to: Oid,
+    pub to_time: Time,
+    pub added: Vec<SnapshotEntry>,
+    pub removed: Vec<SnapshotEntry>,
+}
+
+impl History {
+    /// Compares two commits, or the empty tree with `to` if `from` is `None`.
+    pub fn compare(
+        &self,
+        from: Option<Oid>,
+        to: Oid,
+        time_source: TimeSource,
+    ) -> Result<Comparison> {
+        let repo = self.repository();
+        let layout = self.layout();
+        let from = from.map(|from| repo.find_commit(from)).transpose()?;
+        let to = repo.find_commit(to)?;
+        let from_tree = from.as_ref().map(|from| from.tree()).transpose()?;
+
+        let mut diff_options = DiffOptions::new();
+        diff_options.pathspec(&layout.songs_dir);
+        let diff = repo.diff_tree_to_tree(
+            from_tree.as_ref(),
+            Some(&to.tree()?),
+            Some(&mut diff_options),
+        )?;
+
+        let mut added: Vec<SnapshotEntry> = Vec::new();
+        let mut removed: Vec<SnapshotEntry> = Vec::new();
+        for delta in diff.deltas() {
+            let (list, file) = match delta.status() {
+                Delta::Added => (&mut added, delta.new_file()),
+                Delta::Deleted => (&mut removed, delta.old_file()),
+                _ => continue,
+            };
+            let Some(video_id) = file.path().and_then(|path| layout.video_id(path)) else {
+                continue;
+            };
+            list.push(SnapshotEntry {
+                video_id,
+                info: VideoInfo::from_blob(repo, file.id()),
+            });
+        }
+
+        Ok(Comparison {
+            from: from.as_ref().map(|from| from.id()),
+            from_time: from.as_ref().map(|from| time_source.time(from)),
+            to: to.id(),
+            to_time: time_source.time(&to),
+            added,
+            removed,
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    env, fmt,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    report::Format, DateFormat, Error, IdSelector, LinkStyle, Result, TimeSource, Timezone,
+};
+
+/// Name of the config file in the root of a songs-backup repository.
+pub const REPO_CONFIG: &str = ".songs-history.toml";
+
+/// Settings read from a config file. Options given on the command line take
+/// precedence over them.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Where `report` writes to.
+    pub output: Option<PathBuf>,
+    pub format: Option<Format>,
+    #[serde(deserialize_with = "parse")]
+    pub date_format: Option<DateFormat>,
+    #[serde(deserialize_with = "parse")]
+    pub timezone: Option<Timezone>,
+    pub time_source: Option<TimeSource>,
+    pub songs_dir: Option<PathBuf>,
+    pub summary_path: Option<PathBuf>,
+    pub song_extension: Option<String>,
+    #[serde(deserialize_with = "parse")]
+    pub id_selector: Option<IdSelector>,
+    #[serde(deserialize_with = "parse")]
+    pub link_style: Option<LinkStyle>,
+    pub playlist_id: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        let invalid = |reason: String| Error::ConfigInvalid {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let mut content = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut content))
+            .map_err(|e| invalid(e.to_string()))?;
+        toml::from_str(&content).map_err(|e| invalid(e.message().to_string()))
+    }
+
+    /// Loads the config of the repository at `directory`, or failing that the
+    /// user's, if there is one.
+    pub fn find(directory: &Path) -> Result<Option<Config>> {
+        let candidates = [Some(directory.join(REPO_CONFIG)), user_config_path()];
+        for path in candidates.into_iter().flatten() {
+            match fs::metadata(&path) {
+                Ok(_) => return Config::load(&path).map(Some),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// `songs-history/config.toml` in the XDG config directory, or in the
+/// roaming app data on Windows.
+pub fn user_config_path() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("songs-history").join("config.toml"))
+}
+
+fn parse<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
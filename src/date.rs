@@ -0,0 +1,265 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use git2::Time;
+
+/// How timestamps are rendered in reports.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// `git log`'s default, e.g. `Fri Feb 10 12:00:00 2023 +0100`.
+    #[default]
+    Default,
+    Iso,
+    Rfc2822,
+    /// Age relative to now, e.g. `3 weeks ago`.
+    Relative,
+    /// A strftime pattern.
+    Custom(String),
+}
+
+/// Timezone that timestamps are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timezone {
+    /// The offset recorded in the commit.
+    #[default]
+    Commit,
+    Local,
+    Utc,
+    Named(Tz),
+}
+
+impl DateFormat {
+    pub fn format(&self, time: &Time, timezone: Timezone) -> String {
+        let pattern = match self {
+            DateFormat::Default => "%a %b %e %T %Y %z",
+            DateFormat::Iso => "%Y-%m-%dT%H:%M:%S%:z",
+            DateFormat::Rfc2822 => "%a, %d %b %Y %H:%M:%S %z",
+            DateFormat::Relative => return relative(time.seconds(), Utc::now().timestamp()),
+            DateFormat::Custom(pattern) => pattern,
+        };
+        let utc = DateTime::from_timestamp(time.seconds(), 0).unwrap_or_default();
+        match timezone {
+            Timezone::Commit => {
+                let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+                    .unwrap_or(FixedOffset::east_opt(0).unwrap());
+                render(utc, &offset, pattern)
+            }
+            Timezone::Local => render(utc, &Local, pattern),
+            Timezone::Utc => render(utc, &Utc, pattern),
+            Timezone::Named(tz) => render(utc, &tz, pattern),
+        }
+    }
+}
+
+fn render<Z: TimeZone>(utc: DateTime<Utc>, zone: &Z, pattern: &str) -> String
+where
+    Z::Offset: Display,
+{
+    utc.with_timezone(zone).format(pattern).to_string()
+}
+
+fn relative(seconds: i64, now: i64) -> String {
+    let diff = now - seconds;
+    if diff < 0 {
+        return "in the future".to_string();
+    }
+    let (count, unit) = match diff {
+        d if d < 90 => (d, "second"),
+        d if d < 90 * 60 => (d / 60, "minute"),
+        d if d < 36 * 60 * 60 => (d / (60 * 60), "hour"),
+        d if d < 14 * 24 * 60 * 60 => (d / (24 * 60 * 60), "day"),
+        d if d < 70 * 24 * 60 * 60 => (d / (7 * 24 * 60 * 60), "week"),
+        d if d < 365 * 24 * 60 * 60 => (d / (30 * 24 * 60 * 60), "month"),
+        d => (d / (365 * 24 * 60 * 60), "year"),
+    };
+    match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(DateFormat::Default),
+            "iso" => Ok(DateFormat::Iso),
+            "rfc2822" => Ok(DateFormat::Rfc2822),
+            "relative" => Ok(DateFormat::Relative),
+            _ if StrftimeItems::new(s).any(|item| matches!(item, Item::Error)) => {
+                Err(format!("invalid strftime pattern: {}", s))
+            }
+            _ => Ok(DateFormat::Custom(s.to_string())),
+        }
+    }
+}
+
+impl Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateFormat::Default => write!(f, "default"),
+            DateFormat::Iso => write!(f, "iso"),
+            DateFormat::Rfc2822 => write!(f, "rfc2822"),
+            DateFormat::Relative => write!(f, "relative"),
+            DateFormat::Custom(pattern) => write!(f, "{}", pattern),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "commit" => Ok(Timezone::Commit),
+            "local" => Ok(Timezone::Local),
+            "utc" | "UTC" => Ok(Timezone::Utc),
+            _ => s
+                .parse::<Tz>()
+                .map(Timezone::Named)
+                .map_err(|_| format!("unknown timezone: {}", s)),
+        }
+    }
+}
+
+impl Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timezone::Commit => write!(f, "commit"),
+            Timezone::Local => write!(f, "local"),
+            Timezone::Utc => write!(f, "utc"),
+            Timezone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+/// Parses a date given on the command line into seconds since the epoch.
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD HH:MM[:SS]` and plain
+/// `YYYY-MM-DD`, the latter two in local time. A plain date means the start
+/// of that day.
+pub fn parse_date(s: &str) -> Option<i64> {
+    parse(s, false, &Local)
+}
+
+/// Like [`parse_date`], but a plain date means the last second of that day,
+/// for upper bounds such as `--until`.
+pub fn parse_date_end(s: &str) -> Option<i64> {
+    parse(s, true, &Local)
+}
+
+fn parse<Z: TimeZone>(s: &str, end_of_day: bool, zone: &Z) -> Option<i64> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.timestamp());
+    }
+    if let Some(naive) = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    {
+        return timestamp(&naive, zone);
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    match end_of_day {
+        // Everything before the next midnight, even across a DST change.
+        true => timestamp(&date.succ_opt()?.and_hms_opt(0, 0, 0)?, zone).map(|next| next - 1),
+        false => timestamp(&date.and_hms_opt(0, 0, 0)?, zone),
+    }
+}
+
+fn timestamp<Z: TimeZone>(naive: &NaiveDateTime, zone: &Z) -> Option<i64> {
+    zone.from_local_datetime(naive)
+        .earliest()
+        .map(|date| date.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str, end_of_day: bool) -> Option<i64> {
+        parse(s, end_of_day, &Utc)
+    }
+
+    fn seconds(rfc3339: &str) -> i64 {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().timestamp()
+    }
+
+    #[test]
+    fn plain_dates_are_the_start_or_end_of_the_day() {
+        assert_eq!(
+            utc("2023-06-01", false),
+            Some(seconds("2023-06-01T00:00:00Z"))
+        );
+        assert_eq!(
+            utc("2023-06-01", true),
+            Some(seconds("2023-06-01T23:59:59Z"))
+        );
+        assert_eq!(
+            utc("2023-12-31", true),
+            Some(seconds("2023-12-31T23:59:59Z"))
+        );
+    }
+
+    #[test]
+    fn end_of_day_follows_dst_changes() {
+        // Berlin skips from 02:00 to 03:00 on this day, so it has 23 hours.
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            parse("2023-03-26", false, &berlin),
+            Some(seconds("2023-03-26T00:00:00+01:00"))
+        );
+        assert_eq!(
+            parse("2023-03-26", true, &berlin),
+            Some(seconds("2023-03-26T23:59:59+02:00"))
+        );
+    }
+
+    #[test]
+    fn times_are_taken_as_given() {
+        for end_of_day in [false, true] {
+            assert_eq!(
+                utc("2023-06-01 10:30", end_of_day),
+                Some(seconds("2023-06-01T10:30:00Z"))
+            );
+            assert_eq!(
+                utc("2023-06-01 10:30:15", end_of_day),
+                Some(seconds("2023-06-01T10:30:15Z"))
+            );
+            assert_eq!(
+                utc("2023-06-01T10:30:15", end_of_day),
+                Some(seconds("2023-06-01T10:30:15Z"))
+            );
+        }
+    }
+
+    #[test]
+    fn rfc3339_keeps_its_offset() {
+        assert_eq!(
+            utc("2023-06-01T10:00:00+02:00", true),
+            Some(seconds("2023-06-01T08:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for s in [
+            "",
+            "yesterday",
+            "2023-13-01",
+            "2023-02-30",
+            "2023-06-01 25:00",
+            "01.06.2023",
+        ] {
+            assert_eq!(utc(s, false), None, "{:?} parsed", s);
+            assert_eq!(utc(s, true), None, "{:?} parsed", s);
+        }
+    }
+}
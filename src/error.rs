@@ -0,0 +1,104 @@
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The directory is not a git repository.
+    RepoNotFound {
+        path: PathBuf,
+        source: git2::Error,
+    },
+    /// The output file exists and overwriting was not requested.
+    OutputExists(PathBuf),
+    /// The summary file is missing or could not be parsed.
+    SummaryInvalid(String),
+    /// The songs directory or summary file are not where the layout says.
+    LayoutInvalid(String),
+    /// A revision or date given by the user could not be resolved.
+    InvalidArgument(String),
+    /// The config file could not be read or has invalid values.
+    ConfigInvalid {
+        path: PathBuf,
+        reason: String,
+    },
+    Git(git2::Error),
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Process exit code for this error, so scripts can tell failures apart.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Git(_) | Error::Io(_) | Error::Sqlite(_) => 1,
+            Error::InvalidArgument(_) => 2,
+            Error::RepoNotFound { .. } => 3,
+            Error::OutputExists(_) => 4,
+            Error::SummaryInvalid(_) => 5,
+            Error::LayoutInvalid(_) => 6,
+            Error::ConfigInvalid { .. } => 7,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::RepoNotFound { path, source } => write!(
+                f,
+                "could not open repository at {}: {}",
+                path.display(),
+                source.message()
+            ),
+            Error::OutputExists(path) => write!(
+                f,
+                "output file {} already exists. Use -f, --force to force overwriting the destination",
+                path.display()
+            ),
+            Error::SummaryInvalid(reason) => write!(f, "invalid summary: {}", reason),
+            Error::InvalidArgument(reason) => write!(f, "{}", reason),
+            Error::LayoutInvalid(reason) => write!(f, "unexpected repository layout: {}", reason),
+            Error::ConfigInvalid { path, reason } => {
+                write!(f, "invalid config file {}: {}", path.display(), reason)
+            }
+            Error::Git(e) => write!(f, "git error: {}", e.message()),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Sqlite(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::RepoNotFound { source, .. } => Some(source),
+            Error::Git(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Sqlite(e) => Some(e),
+            Error::OutputExists(_)
+            | Error::SummaryInvalid(_)
+            | Error::InvalidArgument(_)
+            | Error::LayoutInvalid(_)
+            | Error::ConfigInvalid { .. } => None,
+        }
+    }
+}
+
+impl From<git2::Error> for Error {
+    fn from(e: git2::Error) -> Self {
+        Error::Git(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::{self, Write};
+
+use git2::{Oid, Time};
+
+use crate::{html::escape, report::ReportOptions, DateFormat};
+
+/// One commit's worth of changes in an Atom or RSS feed.
+pub(crate) struct FeedEntry {
+    pub commit: Oid,
+    pub time: Time,
+    pub title: String,
+    /// HTML describing the changes.
+    pub content: String,
+}
+
+/// Writes an Atom feed with the newest entry first.
+pub(crate) fn write_atom<W: Write>(
+    writer: &mut W,
+    title: &str,
+    entries: &[FeedEntry],
+    options: &ReportOptions,
+) -> io::Result<()> {
+    let time = |time: &Time| DateFormat::Iso.format(time, options.timezone);
+    let updated = entries
+        .iter()
+        .map(|entry| entry.time)
+        .max_by_key(|time| time.seconds())
+        .unwrap_or(Time::new(0, 0));
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(writer, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#)?;
+    writeln!(writer, "  <title>{}</title>", escape(title))?;
+    match playlist_url(options) {
+        Some(url) => {
+            writeln!(writer, "  <id>{}</id>", escape(&url))?;
+            writeln!(writer, r#"  <link href="{}"/>"#, escape(&url))?;
+        }
+        None => writeln!(writer, "  <id>urn:songs-history:playlist</id>")?,
+    }
+    writeln!(writer, "  <updated>{}</updated>", time(&updated))?;
+    writeln!(writer, "  <author><name>songs-history</name></author>")?;
+    for entry in entries.iter().rev() {
+        writeln!(writer, "  <entry>")?;
+        writeln!(writer, "    <id>urn:sha1:{}</id>", entry.commit)?;
+        writeln!(writer, "    <title>{}</title>", escape(&entry.title))?;
+        writeln!(writer, "    <updated>{}</updated>", time(&entry.time))?;
+        writeln!(
+            writer,
+            r#"    <content type="html">{}</content>"#,
+            escape(&entry.content)
+        )?;
+        writeln!(writer, "  </entry>")?;
+    }
+    writeln!(writer, "</feed>")
+}
+
+/// Writes an RSS 2.0 feed with the newest item first.
+pub(crate) fn write_rss<W: Write>(
+    writer: &mut W,
+    title: &str,
+    entries: &[FeedEntry],
+    options: &ReportOptions,
+) -> io::Result<()> {
+    let time = |time: &Time| DateFormat::Rfc2822.format(time, options.timezone);
+    let link = playlist_url(options).unwrap_or_else(|| "https://www.youtube.com/".to_string());
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(writer, r#"<rss version="2.0">"#)?;
+    writeln!(writer, "<channel>")?;
+    writeln!(writer, "  <title>{}</title>", escape(title))?;
+    writeln!(writer, "  <link>{}</link>", escape(&link))?;
+    writeln!(
+        writer,
+        "  <description>Videos added to and removed from the playlist</description>"
+    )?;
+    if let Some(last) = entries.iter().max_by_key(|entry| entry.time.seconds()) {
+        writeln!(
+            writer,
+            "  <lastBuildDate>{}</lastBuildDate>",
+            time(&last.time)
+        )?;
+    }
+    for entry in entries.iter().rev() {
+        writeln!(writer, "  <item>")?;
+        writeln!(writer, "    <title>{}</title>", escape(&entry.title))?;
+        writeln!(
+            writer,
+            r#"    <guid isPermaLink="false">{}</guid>"#,
+            entry.commit
+        )?;
+        writeln!(writer, "    <pubDate>{}</pubDate>", time(&entry.time))?;
+        writeln!(
+            writer,
+            "    <description>{}</description>",
+            escape(&entry.content)
+        )?;
+        writeln!(writer, "  </item>")?;
+    }
+    writeln!(writer, "</channel>")?;
+    writeln!(writer, "</rss>")
+}
+
+fn playlist_url(options: &ReportOptions) -> Option<String> {
+    let playlist = options.links.playlist.as_ref()?;
+    Some(format!(
+        "https://www.youtube.com/playlist?list={}",
+        playlist
+    ))
+}
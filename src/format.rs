@@ -0,0 +1,280 @@
+use std::{fmt, str::FromStr};
+
+use git2::Time;
+
+use crate::{
+    date::{DateFormat, Timezone},
+    format_duration, VideoInfo,
+};
+
+/// Formats a commit time like `git log` does, in the committer's own offset.
+pub fn format_time(time: &Time) -> String {
+    DateFormat::Default.format(time, Timezone::Commit)
+}
+
+/// Where video links point to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    #[default]
+    Youtube,
+    YoutubeMusic,
+    /// An Invidious instance, given by host or base URL.
+    Invidious(String),
+    /// A Piped instance, given by host or base URL.
+    Piped(String),
+    /// No links at all.
+    None,
+    /// A URL with `{id}` and optionally `{playlist}` placeholders.
+    Template(String),
+}
+
+impl FromStr for LinkStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (style, argument) = match s.split_once(':') {
+            Some((style, argument)) => (style, Some(argument)),
+            None => (s, None),
+        };
+        match (style, argument) {
+            ("youtube", None) => Ok(LinkStyle::Youtube),
+            ("youtube-music", None) => Ok(LinkStyle::YoutubeMusic),
+            ("none", None) => Ok(LinkStyle::None),
+            ("invidious", Some(host)) if !host.is_empty() => {
+                Ok(LinkStyle::Invidious(host.to_string()))
+            }
+            ("piped", Some(host)) if !host.is_empty() => Ok(LinkStyle::Piped(host.to_string())),
+            ("template", Some(pattern)) if pattern.contains("{id}") => {
+                Ok(LinkStyle::Template(pattern.to_string()))
+            }
+            ("template", _) => Err("a link template needs an {id} placeholder".to_string()),
+            _ => Err(format!(
+                "unknown link style {}, expected youtube, youtube-music, invidious:<host>, \
+                 piped:<host>, none or template:<pattern>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LinkStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkStyle::Youtube => write!(f, "youtube"),
+            LinkStyle::YoutubeMusic => write!(f, "youtube-music"),
+            LinkStyle::Invidious(host) => write!(f, "invidious:{}", host),
+            LinkStyle::Piped(host) => write!(f, "piped:{}", host),
+            LinkStyle::None => write!(f, "none"),
+            LinkStyle::Template(pattern) => write!(f, "template:{}", pattern),
+        }
+    }
+}
+
+/// How to link to videos, optionally in the context of a playlist.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Links {
+    pub style: LinkStyle,
+    /// Id of the playlist the links open in.
+    pub playlist: Option<String>,
+}
+
+impl Links {
+    /// The link to a video, or `None` with [`LinkStyle::None`].
+    pub fn url(&self, video: &str) -> Option<String> {
+        let url = match &self.style {
+            LinkStyle::Youtube if self.playlist.is_none() => video_url(video),
+            LinkStyle::Youtube => format!("https://www.youtube.com/watch?v={}", video),
+            LinkStyle::YoutubeMusic => format!("https://music.youtube.com/watch?v={}", video),
+            LinkStyle::Invidious(host) | LinkStyle::Piped(host) => {
+                format!("{}/watch?v={}", base_url(host), video)
+            }
+            LinkStyle::None => return None,
+            LinkStyle::Template(pattern) if pattern.contains("{playlist}") => {
+                let playlist = self.playlist.as_deref().unwrap_or("");
+                return Some(
+                    pattern
+                        .replace("{id}", video)
+                        .replace("{playlist}", playlist),
+                );
+            }
+            LinkStyle::Template(pattern) => pattern.replace("{id}", video),
+        };
+        Some(self.with_playlist(url))
+    }
+
+    fn with_playlist(&self, url: String) -> String {
+        match &self.playlist {
+            Some(playlist) if url.contains('?') => format!("{}&list={}", url, playlist),
+            Some(playlist) => format!("{}?list={}", url, playlist),
+            None => url,
+        }
+    }
+}
+
+fn base_url(host: &str) -> String {
+    match host.contains("://") {
+        true => host.trim_end_matches('/').to_string(),
+        false => format!("https://{}", host.trim_end_matches('/')),
+    }
+}
+
+/// Formats a video as a Markdown link, or just its id without links.
+pub fn format_video(video: &str, links: &Links) -> String {
+    match links.url(video) {
+        Some(url) => format!("[{}]({})", video, url),
+        None => video.to_string(),
+    }
+}
+
+/// Formats a video as a Markdown link labelled with its title, followed by
+/// the channel and length when known. Falls back to [`format_video`].
+pub fn describe_video(video: &str, info: &VideoInfo, links: &Links) -> String {
+    let Some(title) = &info.title else {
+        return format_video(video, links);
+    };
+    let mut description = match links.url(video) {
+        Some(url) => format!("[\"{}\"]({})", escape_link_text(title), url),
+        None => format!("\"{}\"", title),
+    };
+    if let Some(channel) = &info.channel {
+        description.push_str(&format!(" by {}", channel));
+    }
+    if let Some(duration) = info.duration {
+        description.push_str(&format!(" ({})", format_duration(duration)));
+    }
+    description
+}
+
+/// Escapes the characters that would end or break Markdown link text.
+fn escape_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The short YouTube link to a video.
+pub fn video_url(video: &str) -> String {
+    format!("https://youtu.be/{}", video)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_videos_with_their_details() {
+        let info = VideoInfo {
+            title: Some("Song".to_string()),
+            channel: Some("Channel".to_string()),
+            duration: Some(212),
+        };
+        assert_eq!(
+            describe_video("aaaaaaaaaaa", &info, &Links::default()),
+            "[\"Song\"](https://youtu.be/aaaaaaaaaaa) by Channel (3:32)"
+        );
+        assert_eq!(
+            describe_video("aaaaaaaaaaa", &VideoInfo::default(), &Links::default()),
+            "[aaaaaaaaaaa](https://youtu.be/aaaaaaaaaaa)"
+        );
+    }
+
+    #[test]
+    fn escapes_titles_in_link_text() {
+        let info = VideoInfo {
+            title: Some(r"Song :] [live] \o/".to_string()),
+            ..VideoInfo::default()
+        };
+        assert_eq!(
+            describe_video("aaaaaaaaaaa", &info, &Links::default()),
+            r#"["Song :\] \[live\] \\o/"](https://youtu.be/aaaaaaaaaaa)"#
+        );
+    }
+
+    #[test]
+    fn parses_link_styles() {
+        let style = |s: &str| s.parse::<LinkStyle>();
+        assert_eq!(style("youtube"), Ok(LinkStyle::Youtube));
+        assert_eq!(style("youtube-music"), Ok(LinkStyle::YoutubeMusic));
+        assert_eq!(style("none"), Ok(LinkStyle::None));
+        assert_eq!(
+            style("invidious:yewtu.be"),
+            Ok(LinkStyle::Invidious("yewtu.be".to_string()))
+        );
+        assert_eq!(
+            style("piped:https://piped.video/"),
+            Ok(LinkStyle::Piped("https://piped.video/".to_string()))
+        );
+        assert_eq!(
+            style("template:https://example.com/{id}"),
+            Ok(LinkStyle::Template("https://example.com/{id}".to_string()))
+        );
+        for s in [
+            "",
+            "vimeo",
+            "youtube:x",
+            "invidious",
+            "piped:",
+            "template:https://x",
+        ] {
+            assert!(style(s).is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn links_without_a_playlist() {
+        let url = |style: &str| {
+            let links = Links {
+                style: style.parse().unwrap(),
+                playlist: None,
+            };
+            links.url("aaaaaaaaaaa")
+        };
+        assert_eq!(url("youtube").unwrap(), "https://youtu.be/aaaaaaaaaaa");
+        assert_eq!(
+            url("youtube-music").unwrap(),
+            "https://music.youtube.com/watch?v=aaaaaaaaaaa"
+        );
+        assert_eq!(
+            url("invidious:yewtu.be").unwrap(),
+            "https://yewtu.be/watch?v=aaaaaaaaaaa"
+        );
+        assert_eq!(
+            url("piped:http://localhost:8080/").unwrap(),
+            "http://localhost:8080/watch?v=aaaaaaaaaaa"
+        );
+        assert_eq!(
+            url("template:https://example.com/v/{id}?list={playlist}").unwrap(),
+            "https://example.com/v/aaaaaaaaaaa?list="
+        );
+        assert_eq!(url("none"), None);
+    }
+
+    #[test]
+    fn links_in_a_playlist() {
+        let url = |style: &str| {
+            let links = Links {
+                style: style.parse().unwrap(),
+                playlist: Some("PL123".to_string()),
+            };
+            links.url("aaaaaaaaaaa")
+        };
+        assert_eq!(
+            url("youtube").unwrap(),
+            "https://www.youtube.com/watch?v=aaaaaaaaaaa&list=PL123"
+        );
+        assert_eq!(
+            url("template:https://example.com/v/{id}").unwrap(),
+            "https://example.com/v/aaaaaaaaaaa?list=PL123"
+        );
+        assert_eq!(
+            url("template:https://example.com/{playlist}/{id}").unwrap(),
+            "https://example.com/PL123/aaaaaaaaaaa"
+        );
+        assert_eq!(url("none"), None);
+    }
+}
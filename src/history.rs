@@ -0,0 +1,415 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::Path,
+    vec,
+};
+
+use clap::ValueEnum;
+use git2::{
+    Commit,
+    Delta::{Added, Deleted, Modified},
+    ErrorCode, Oid, Repository, Time,
+};
+use serde::Deserialize;
+
+use crate::{
+    read_ids, video::read_json, Availability, Error, FieldChange, Layout, Result, Selector,
+    VideoInfo,
+};
+
+/// A songs-backup repository.
+pub struct History {
+    repo: Repository,
+    layout: Layout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    Added,
+    Removed,
+    /// Added again after an earlier removal. Only reported with
+    /// [`Options::full_history`].
+    ReAdded,
+    /// A watched field of the song file changed. Only reported with
+    /// [`Options::modifications`].
+    Modified,
+    /// The video turned private or was deleted. Only reported with
+    /// [`Options::availability`].
+    BecameUnavailable,
+    /// The video can be watched again. Only reported with
+    /// [`Options::availability`].
+    BecameAvailable,
+}
+
+/// A video being added to or removed from the playlist in some commit.
+#[derive(Clone, Debug)]
+pub struct HistoryEvent {
+    pub kind: EventKind,
+    pub video_id: String,
+    pub commit: Oid,
+    pub time: Time,
+    /// Whether the event comes from a root commit, i.e. the initial import.
+    pub initial: bool,
+    /// For [`EventKind::ReAdded`], when the video was last removed.
+    pub removed_at: Option<Time>,
+    /// Details from the song file as added, or as it was before removal.
+    pub info: VideoInfo,
+    /// For [`EventKind::Modified`], the watched fields that changed.
+    pub changes: Vec<FieldChange>,
+    /// For [`EventKind::BecameUnavailable`], what happened to the video.
+    pub availability: Option<Availability>,
+}
+
+/// Which of a commit's timestamps events are dated with.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeSource {
+    /// When the commit was made
+    #[default]
+    Committer,
+    /// When the change was originally authored
+    Author,
+}
+
+impl TimeSource {
+    pub fn time(self, commit: &Commit) -> Time {
+        match self {
+            TimeSource::Committer => commit.time(),
+            TimeSource::Author => commit.author().when(),
+        }
+    }
+}
+
+/// Controls which events [`History::events`] yields.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Report the contents of the root commit as added.
+    pub include_initial: bool,
+    /// Report every add, removal and re-add instead of only the first add
+    /// and the removals of videos that are gone today.
+    pub full_history: bool,
+    /// Only report commits after this one.
+    pub from: Option<Oid>,
+    /// Walk back from this commit instead of HEAD.
+    pub to: Option<Oid>,
+    /// Only report commits made at or after this time, in seconds.
+    pub since: Option<i64>,
+    /// Only report commits made at or before this time, in seconds.
+    pub until: Option<i64>,
+    /// Which timestamp events and the date filters use.
+    pub time_source: TimeSource,
+    /// Report changes to the [`Options::watch_fields`] of song files.
+    pub modifications: bool,
+    pub watch_fields: Vec<Selector>,
+    /// Report videos turning private or deleted, and back.
+    pub availability: bool,
+}
+
+/// What [`Events`] remembers about the commits it has walked, so that a later
+/// walk can pick up where it stopped.
+#[derive(Clone, Debug, Default)]
+pub struct State {
+    pub already_added: HashSet<String>,
+    /// When each video was last removed.
+    pub removed_at: HashMap<String, Time>,
+}
+
+/// Iterator over the events of a [`History`], oldest first.
+///
+/// Unless [`Options::full_history`] is set, a video is only reported as added
+/// the first time it appears, and removals of videos that are in the playlist
+/// again today (or at [`Options::to`]) are skipped.
+pub struct Events<'repo> {
+    repo: &'repo Repository,
+    layout: &'repo Layout,
+    options: Options,
+    commits: vec::IntoIter<(Oid, Step)>,
+    pending: VecDeque<HistoryEvent>,
+    state: State,
+    tip: Oid,
+    current_ids: HashSet<String>,
+}
+
+/// What [`Events`] does with a commit it walks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Outside the range: only replayed to build the state.
+    Replay,
+    Report,
+}
+
+impl History {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<History> {
+        let path = path.as_ref();
+        match Repository::open(path) {
+            Ok(repo) => Ok(History {
+                repo,
+                layout: Layout::default(),
+            }),
+            Err(e) if e.code() == ErrorCode::NotFound => Err(Error::RepoNotFound {
+                path: path.to_path_buf(),
+                source: e,
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Uses a layout other than songs-backup's own.
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn repository(&self) -> &Repository {
+        &self.repo
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Checks the layout against HEAD.
+    pub fn validate_layout(&self) -> Result<()> {
+        self.layout.validate(&self.repo.head()?.peel_to_commit()?)
+    }
+
+    /// Resolves any revspec git understands to a commit.
+    pub fn resolve(&self, spec: &str) -> Result<Oid> {
+        self.repo
+            .revparse_single(spec)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|e| {
+                Error::InvalidArgument(format!("unknown revision {}: {}", spec, e.message()))
+            })
+    }
+
+    /// The commit a walk with these options starts from.
+    pub fn tip(&self, options: &Options) -> Result<Oid> {
+        match options.to {
+            Some(oid) => Ok(oid),
+            None => Ok(self.repo.head()?.peel_to_commit()?.id()),
+        }
+    }
+
+    /// Whether `ancestor` is `commit` or one of its ancestors.
+    pub fn is_ancestor(&self, ancestor: Oid, commit: Oid) -> Result<bool> {
+        Ok(ancestor == commit || self.repo.graph_descendant_of(commit, ancestor)?)
+    }
+
+    pub fn events(&self, options: &Options) -> Result<Events<'_>> {
+        self.walk(options, None)
+    }
+
+    /// Continues from the state of an earlier walk that ended at
+    /// [`Options::from`], walking only the commits after it.
+    pub fn resume(&self, options: &Options, state: State) -> Result<Events<'_>> {
+        self.walk(options, Some(state))
+    }
+
+    fn walk(&self, options: &Options, state: Option<State>) -> Result<Events<'_>> {
+        let tip = self.repo.find_commit(self.tip(options)?)?;
+
+        // Without an earlier state, commits outside the range are still
+        // walked so that adds and removals before it are known, but their
+        // events are dropped.
+        let mut before = HashSet::new();
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(tip.id())?;
+        if let Some(from) = options.from {
+            match state {
+                Some(_) => revwalk.hide(from)?,
+                None => {
+                    let mut revwalk = self.repo.revwalk()?;
+                    revwalk.push(from)?;
+                    for oid in revwalk {
+                        before.insert(oid?);
+                    }
+                }
+            }
+        }
+        let filtered = options.since.is_some() || options.until.is_some();
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            let oid = oid?;
+            let in_dates = !filtered || {
+                let time = options
+                    .time_source
+                    .time(&self.repo.find_commit(oid)?)
+                    .seconds();
+                options.since.is_none_or(|since| time >= since)
+                    && options.until.is_none_or(|until| time <= until)
+            };
+            let step = match in_dates && !before.contains(&oid) {
+                true => Step::Report,
+                false => Step::Replay,
+            };
+            commits.push((oid, step));
+        }
+        commits.reverse();
+        // Nothing after the last reported commit can change what is reported.
+        while commits
+            .last()
+            .is_some_and(|&(_, step)| step != Step::Report)
+        {
+            commits.pop();
+        }
+
+        Ok(Events {
+            repo: &self.repo,
+            layout: &self.layout,
+            options: options.clone(),
+            commits: commits.into_iter(),
+            pending: VecDeque::new(),
+            state: state.unwrap_or_default(),
+            tip: tip.id(),
+            current_ids: read_ids(&self.repo, &tip, &self.layout)?,
+        })
+    }
+}
+
+impl Events<'_> {
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// The newest commit of the walk.
+    pub fn tip(&self) -> Oid {
+        self.tip
+    }
+
+    fn process(&mut self, oid: Oid, step: Step) -> Result<()> {
+        let commit = self.repo.find_commit(oid)?;
+        let time = self.options.time_source.time(&commit);
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            // The full history needs to know what was there from the start
+            // to recognise re-adds, even if the import itself is not shown.
+            Err(_) if self.options.include_initial || self.options.full_history => None,
+            Err(_) => return Ok(()),
+        };
+        let initial = parent_tree.is_none();
+        let report = step == Step::Report && (!initial || self.options.include_initial);
+
+        let diff =
+            self.repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+        let mut added: Vec<HistoryEvent> = Vec::new();
+        let mut deleted: Vec<HistoryEvent> = Vec::new();
+        let mut modified: Vec<HistoryEvent> = Vec::new();
+        let event = |kind, video: &str, blob: Oid| HistoryEvent {
+            kind,
+            video_id: video.to_string(),
+            commit: oid,
+            time,
+            initial,
+            removed_at: None,
+            changes: Vec::new(),
+            availability: None,
+            info: match report {
+                true => VideoInfo::from_blob(self.repo, blob),
+                false => VideoInfo::default(),
+            },
+        };
+
+        for delta in diff.deltas() {
+            if !matches!(delta.status(), Added | Deleted | Modified) {
+                continue;
+            }
+            let Some(video) = delta
+                .new_file()
+                .path()
+                .and_then(|path| self.layout.video_id(path))
+            else {
+                continue;
+            };
+            let blob = match delta.status() {
+                Deleted => delta.old_file().id(),
+                _ => delta.new_file().id(),
+            };
+            match delta.status() {
+                Added if self.options.full_history => {
+                    if self.state.already_added.insert(video.clone()) {
+                        added.push(event(EventKind::Added, &video, blob));
+                    } else {
+                        added.push(HistoryEvent {
+                            removed_at: self.state.removed_at.get(&video).copied(),
+                            ..event(EventKind::ReAdded, &video, blob)
+                        });
+                    }
+                }
+                Added => {
+                    if self.state.already_added.contains(&video) {
+                        continue;
+                    }
+                    self.state.already_added.insert(video.clone());
+                    added.push(event(EventKind::Added, &video, blob));
+                }
+                Deleted if self.options.full_history => {
+                    self.state.removed_at.insert(video.clone(), time);
+                    deleted.push(event(EventKind::Removed, &video, blob));
+                }
+                Deleted => {
+                    if self.current_ids.contains(&video) {
+                        continue;
+                    }
+                    deleted.push(event(EventKind::Removed, &video, blob));
+                }
+                Modified if report && (self.options.modifications || self.options.availability) => {
+                    let old_blob = delta.old_file().id();
+                    let (Some(old), Some(new)) =
+                        (read_json(self.repo, old_blob), read_json(self.repo, blob))
+                    else {
+                        continue;
+                    };
+                    let (before, after) = (Availability::of(&old), Availability::of(&new));
+                    if self.options.availability && before.is_available() != after.is_available() {
+                        modified.push(match after.is_available() {
+                            true => event(EventKind::BecameAvailable, &video, blob),
+                            // The old file still has the title and channel.
+                            false => HistoryEvent {
+                                availability: Some(after),
+                                ..event(EventKind::BecameUnavailable, &video, old_blob)
+                            },
+                        });
+                    }
+                    if !self.options.modifications {
+                        continue;
+                    }
+                    let changes = FieldChange::between(&old, &new, &self.options.watch_fields);
+                    if !changes.is_empty() {
+                        modified.push(HistoryEvent {
+                            changes,
+                            ..event(EventKind::Modified, &video, blob)
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if report {
+            self.pending.extend(added);
+            self.pending.extend(deleted);
+            self.pending.extend(modified);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<HistoryEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            let (oid, step) = self.commits.next()?;
+            if let Err(e) = self.process(oid, step) {
+                return Some(Err(e));
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crate::{format_duration, EventKind, Links, VideoInfo};
+
+const HEADER: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body { font-family: system-ui, sans-serif; max-width: 60rem; margin: 0 auto; padding: 1rem; color: #222; }
+#filter { width: 100%; box-sizing: border-box; padding: 0.5rem; font-size: 1rem; margin-bottom: 1rem; }
+summary { font-weight: bold; cursor: pointer; padding: 0.25rem 0; }
+ul { list-style: none; padding: 0; }
+li { display: flex; align-items: center; gap: 0.75rem; margin: 0.25rem 0; padding: 0.25rem; border-left: 4px solid #ccc; }
+li img { width: 96px; height: 54px; object-fit: cover; flex-shrink: 0; }
+li small { color: #666; }
+.kind { font-weight: bold; }
+.added, .re-added, .became-available { border-color: #2e7d32; background: #e8f5e9; }
+.added .kind, .re-added .kind, .became-available .kind { color: #2e7d32; }
+.removed, .became-unavailable, .unavailable { border-color: #c62828; background: #ffebee; }
+.removed .kind, .became-unavailable .kind, .unavailable .kind { color: #c62828; }
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<input id="filter" type="search" placeholder="Filter by title, channel or id">
+"#;
+
+const FOOTER: &str = r#"<script>
+const filter = document.getElementById("filter");
+filter.addEventListener("input", () => {
+  const query = filter.value.toLowerCase();
+  for (const item of document.querySelectorAll("li[data-search]")) {
+    item.hidden = !item.dataset.search.includes(query);
+  }
+  for (const section of document.querySelectorAll("details")) {
+    section.hidden = !section.querySelector("li:not([hidden])");
+    if (query) {
+      section.open = true;
+    }
+  }
+});
+</script>
+</body>
+</html>
+"#;
+
+/// Escapes text for use in HTML content and quoted attributes.
+pub(crate) fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Starts a page with the styles and the filter box.
+pub(crate) fn write_header<W: Write>(writer: &mut W, title: &str) -> io::Result<()> {
+    writer.write_all(HEADER.replace("{title}", &escape(title)).as_bytes())
+}
+
+/// Ends a page with the script behind the filter box.
+pub(crate) fn write_footer<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(FOOTER.as_bytes())
+}
+
+/// Writes a list item with the thumbnail and details of a video, styled by
+/// `class`. `kind` and `note` are shown before and after the details.
+pub(crate) fn write_video<W: Write>(
+    writer: &mut W,
+    class: &str,
+    kind: Option<EventKind>,
+    video: &str,
+    info: &VideoInfo,
+    links: &Links,
+    note: &str,
+) -> io::Result<()> {
+    let search = [Some(video), info.title.as_deref(), info.channel.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    write!(
+        writer,
+        "<li class=\"{}\" data-search=\"{}\">",
+        class,
+        escape(&search)
+    )?;
+    write!(
+        writer,
+        "<img src=\"https://i.ytimg.com/vi/{}/hqdefault.jpg\" alt=\"\" loading=\"lazy\"><div>",
+        escape(video)
+    )?;
+    if let Some(kind) = kind {
+        write!(writer, "<span class=\"kind\">{}</span> ", kind.label())?;
+    }
+    write!(writer, "{}", describe_video(video, info, links))?;
+    if !note.is_empty() {
+        write!(writer, "<br><small>{}</small>", escape(note))?;
+    }
+    writeln!(writer, "</div></li>")
+}
+
+/// Like [`crate::describe_video`], but as HTML.
+pub(crate) fn describe_video(video: &str, info: &VideoInfo, links: &Links) -> String {
+    let title = match &info.title {
+        Some(title) => escape(title),
+        None => escape(video),
+    };
+    let mut description = match links.url(video) {
+        Some(url) => format!("<a href=\"{}\">{}</a>", escape(&url), title),
+        None => title.into_owned(),
+    };
+    if let Some(channel) = &info.channel {
+        description.push_str(&format!(" by {}", escape(channel)));
+    }
+    if let Some(duration) = info.duration {
+        description.push_str(&format!(" ({})", format_duration(duration)));
+    }
+    description
+}
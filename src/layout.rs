@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use git2::{Commit, ObjectType};
+
+use crate::{Error, Result, Selector};
+
+/// Where a songs-backup repository keeps its files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Directory with one file per video, named after the video id.
+    pub songs_dir: PathBuf,
+    /// File with the playlist items as returned by the YouTube API.
+    pub summary_path: PathBuf,
+    /// Extension of the files in `songs_dir`, without the dot.
+    pub song_extension: String,
+    /// Where the video ids are in the summary; detected when `None`.
+    pub id_selector: Option<Selector>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            songs_dir: PathBuf::from("output/songs"),
+            summary_path: PathBuf::from("output/summary.json"),
+            song_extension: "json".to_string(),
+            id_selector: None,
+        }
+    }
+}
+
+impl Layout {
+    /// The video id of a song file, or `None` if `path` is not one.
+    pub fn video_id(&self, path: &Path) -> Option<String> {
+        if !path.starts_with(&self.songs_dir) {
+            return None;
+        }
+        let extension = path.extension().unwrap_or_default();
+        if extension != self.song_extension.as_str() {
+            return None;
+        }
+        Some(path.file_stem()?.to_string_lossy().into_owned())
+    }
+
+    pub fn song_path(&self, video: &str) -> PathBuf {
+        let mut path = self.songs_dir.join(video);
+        if !self.song_extension.is_empty() {
+            path.set_extension(&self.song_extension);
+        }
+        path
+    }
+
+    /// Checks that the songs directory and summary file exist in `commit`.
+    pub fn validate(&self, commit: &Commit) -> Result<()> {
+        let tree = commit.tree()?;
+        let songs_dir = tree.get_path(&self.songs_dir);
+        if songs_dir.map(|entry| entry.kind()) != Ok(Some(ObjectType::Tree)) {
+            return Err(Error::LayoutInvalid(format!(
+                "songs directory {} not found in commit {}",
+                self.songs_dir.display(),
+                commit.id()
+            )));
+        }
+        let summary = tree.get_path(&self.summary_path);
+        if summary.map(|entry| entry.kind()) != Ok(Some(ObjectType::Blob)) {
+            return Err(Error::LayoutInvalid(format!(
+                "summary file {} not found in commit {}",
+                self.summary_path.display(),
+                commit.id()
+            )));
+        }
+        Ok(())
+    }
+}
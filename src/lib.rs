@@ -0,0 +1,44 @@
+//! Reconstructs the history of a YouTube playlist from a songs-backup git
+//! repository.
+
+mod availability;
+mod changes;
+mod checkpoint;
+mod compare;
+mod config;
+mod date;
+mod error;
+mod feed;
+mod format;
+mod history;
+mod html;
+mod layout;
+pub mod output;
+pub mod report;
+mod selector;
+mod snapshot;
+mod sqlite;
+mod stats;
+mod summary;
+mod timeline;
+mod video;
+
+pub use availability::{Availability, UnavailableVideo};
+pub use changes::FieldChange;
+pub use checkpoint::{Checkpoint, Rules};
+pub use compare::Comparison;
+pub use config::{user_config_path, Config, REPO_CONFIG};
+pub use date::{parse_date, parse_date_end, DateFormat, Timezone};
+pub use error::{Error, Result};
+pub use format::{describe_video, format_time, format_video, video_url, LinkStyle, Links};
+pub use history::{EventKind, Events, History, HistoryEvent, Options, State, TimeSource};
+pub use layout::Layout;
+pub use selector::{IdSelector, Selector};
+pub use snapshot::{Snapshot, SnapshotEntry};
+pub use sqlite::Export;
+pub use stats::{Growth, Stats, Survivor};
+pub use summary::{
+    detect_selector, get_current_ids, read_ids, read_playlist, read_summary, song_ids,
+};
+pub use timeline::{format_span, Timeline};
+pub use video::{format_duration, VideoInfo};
@@ -1,172 +1,728 @@
 use std::{
-    collections::HashSet,
-    fs::OpenOptions,
-    io::{BufWriter, Read, Write},
+    fs::File,
+    io::{self, BufWriter},
     path::{Path, PathBuf},
+    process::ExitCode,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use clap::Parser;
-use git2::{
-    Delta::{Added, Deleted},
-    Repository, Time,
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgMatches, Args as _, CommandFactory, FromArgMatches,
+    Parser, Subcommand,
+};
+use songs_history::{
+    detect_selector,
+    output::Output,
+    parse_date, parse_date_end, read_playlist, read_summary,
+    report::{self, Format, ReportOptions, ReportWriter, StatsFormat},
+    song_ids, Checkpoint, Config, DateFormat, Error, History, IdSelector, Layout, LinkStyle, Links,
+    Options, Result, Rules, Selector, TimeSource, Timezone,
 };
-use serde_json::Deserializer;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    arg_required_else_help = true
+)]
 struct Args {
-    /// Path to the songs-backup git repository
-    directory: PathBuf,
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Without a subcommand, the arguments of `report`.
+    #[command(flatten)]
+    report: ReportArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write the videos added to and removed from the playlist
+    Report(ReportArgs),
+    /// List the videos that were in the playlist at a date or revision
+    Snapshot(SnapshotArgs),
+    /// Show the net videos added and removed between two dates or revisions
+    Diff(DiffArgs),
+    /// Show every change to one video and how long it was in the playlist
+    Show(ShowArgs),
+    /// Show totals, growth and survival statistics over the whole history
+    Stats(StatsArgs),
+    /// Check that the repository matches the layout and its summary is valid
+    Check,
+    /// Write the commits, videos and events to a SQLite database, adding to
+    /// an earlier export
+    ExportSqlite(ExportArgs),
+}
+
+// Options shared by all subcommands.
+#[derive(clap::Args, Debug)]
+struct GlobalArgs {
+    /// Path to the songs-backup git repository [default: .]
+    #[arg(short = 'C', long, value_name = "PATH", global = true)]
+    repo: Option<PathBuf>,
+
+    /// Config file to use instead of .songs-history.toml in the repository or
+    /// songs-history/config.toml in the user's config directory
+    #[arg(long, value_name = "PATH", global = true)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    dates: DateArgs,
+
+    #[command(flatten)]
+    layout: LayoutArgs,
+
+    #[command(flatten)]
+    links: LinkArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReportArgs {
+    /// Path to the songs-backup git repository, instead of --repo
+    #[arg(conflicts_with = "repo")]
+    directory: Option<PathBuf>,
+
+    /// Where to write the report, or - for stdout
+    #[arg(short, long, default_value = "output.txt")]
+    output: PathBuf,
 
     /// Overwrite the output file
     #[arg(short, long)]
     force: bool,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// Report the videos of the first backup as added
+    #[arg(long)]
+    include_initial: bool,
+
+    /// Report every add, removal and re-add, including those of videos that
+    /// are in the playlist today
+    #[arg(long)]
+    full_history: bool,
+
+    /// Only report commits after this revision
+    #[arg(long, value_name = "REV")]
+    from: Option<String>,
+
+    /// Report up to this revision instead of HEAD
+    #[arg(long, value_name = "REV")]
+    to: Option<String>,
+
+    /// Only report commits made at or after this date
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    /// Only report commits made at or before this date
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+
+    /// Only process commits made since the last incremental run and append
+    /// them to the output. Progress is kept next to it in <OUTPUT>.state
+    #[arg(long, conflicts_with_all = ["from", "since", "until"])]
+    incremental: bool,
+
+    /// Report changes to the watched fields of song files
+    #[arg(long)]
+    modifications: bool,
+
+    /// Fields of the song files to watch for changes
+    #[arg(
+        long,
+        value_name = "FIELD",
+        value_delimiter = ',',
+        default_value = "snippet.title,snippet.videoOwnerChannelTitle,status.privacyStatus"
+    )]
+    watch_fields: Vec<Selector>,
+
+    /// Report videos becoming private or deleted and list the ones that are
+    /// unavailable now
+    #[arg(long, conflicts_with = "incremental")]
+    availability: bool,
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
+#[derive(clap::Args, Debug)]
+struct SnapshotArgs {
+    /// Revision or date to list the playlist at, a plain date meaning the end
+    /// of that day
+    #[arg(long, value_name = "DATE|REV", default_value = "HEAD")]
+    at: String,
 
-    let repo = match Repository::open(args.directory) {
-        Ok(repo) => repo,
-        Err(e) => panic!("failed to open: {}", e),
-    };
+    /// Where to write the list, or - for stdout
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
 
-    let mut revwalk = repo.revwalk().unwrap();
-    revwalk.push_head().unwrap();
-
-    let file = match OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create_new(!args.force)
-        .create(args.force)
-        .open("output.txt")
-    {
-        Ok(f) => f,
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::AlreadyExists => {
-                panic!("Output file output.txt already exists. Use -f, --force to force overwriting the destination");
-            }
-            _ => panic!("failed to open file: {}", e),
-        },
-    };
-    let mut writer = BufWriter::new(file);
-    writeln!(writer, "# songs-history")?;
+    /// Overwrite the output file
+    #[arg(short, long)]
+    force: bool,
 
-    let current_ids = get_current_ids(&repo).unwrap();
+    /// Format of the list
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+}
 
-    let mut already_added: HashSet<String> = HashSet::new();
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Revision or date to compare from, a plain date meaning the end of
+    /// that day. A date before the first backup compares with an empty
+    /// playlist
+    #[arg(value_name = "DATE|REV")]
+    from: String,
 
-    for commit in revwalk.collect::<Vec<_>>().iter().rev() {
-        let commit = repo.find_commit(*commit.as_ref().unwrap()).unwrap();
-        let parent = match commit.parent(0) {
-            Ok(parent) => parent,
-            Err(_) => continue,
-        };
+    /// Revision or date to compare to
+    #[arg(value_name = "DATE|REV", default_value = "HEAD")]
+    to: String,
+
+    /// Where to write the changes, or - for stdout
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
+
+    /// Overwrite the output file
+    #[arg(short, long)]
+    force: bool,
 
-        let diff = repo
-            .diff_tree_to_tree(
-                Some(&parent.tree().unwrap()),
-                Some(&commit.tree().unwrap()),
-                None,
-            )
-            .unwrap();
+    /// Format of the changes
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+}
 
-        let mut added: Vec<String> = Vec::new();
-        let mut deleted: Vec<String> = Vec::new();
+#[derive(clap::Args, Debug)]
+struct ShowArgs {
+    /// Id of the video to show
+    video_id: String,
 
-        for delta in diff.deltas() {
-            if !matches!(delta.status(), Added | Deleted) {
-                continue;
-            }
-            let new_file = delta.new_file();
-            let path = new_file.path().unwrap();
-            if !path.starts_with("output/songs") {
-                continue;
-            }
-            let video = path.file_stem().unwrap().to_string_lossy();
-            match delta.status() {
-                Added => {
-                    if already_added.contains(&video.to_string()) {
-                        continue;
-                    }
-                    already_added.insert(video.to_string());
-                    added.push(video.to_string());
-                }
-                Deleted => {
-                    if current_ids.contains(&video.to_string()) {
-                        continue;
-                    }
-                    deleted.push(video.to_string());
-                }
-                _ => {}
-            }
+    /// Where to write the history, or - for stdout
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
+
+    /// Overwrite the output file
+    #[arg(short, long)]
+    force: bool,
+
+    /// Format of the history
+    #[arg(long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    /// Where to write the statistics, or - for stdout
+    #[arg(short, long, default_value = "-")]
+    output: PathBuf,
+
+    /// Overwrite the output file
+    #[arg(short, long)]
+    force: bool,
+
+    /// Format of the statistics
+    #[arg(long, value_enum, default_value_t = StatsFormat::Table)]
+    format: StatsFormat,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExportArgs {
+    /// Database file to create or update
+    database: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct DateArgs {
+    /// How to show dates: default, iso, rfc2822, relative or a strftime pattern
+    #[arg(long, global = true, default_value = "default")]
+    date_format: DateFormat,
+
+    /// Timezone to show dates in: commit, local, utc or an IANA name such as
+    /// Europe/Berlin
+    #[arg(long, global = true, default_value = "commit")]
+    timezone: Timezone,
+
+    /// Which commit timestamp to use
+    #[arg(long, global = true, value_enum, default_value_t = TimeSource::Committer)]
+    time_source: TimeSource,
+}
+
+#[derive(clap::Args, Debug)]
+struct LayoutArgs {
+    /// Directory of the repository that holds one file per video
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        default_value = "output/songs"
+    )]
+    songs_dir: PathBuf,
+
+    /// File of the repository with the current playlist items
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        default_value = "output/summary.json"
+    )]
+    summary_path: PathBuf,
+
+    /// Extension of the files in the songs directory
+    #[arg(long, global = true, value_name = "EXT", default_value = "json")]
+    song_extension: String,
+
+    /// Where the video ids are in the summary, such as
+    /// items[].contentDetails.videoId, or auto to detect it
+    #[arg(long, global = true, value_name = "SELECTOR", default_value = "auto")]
+    id_selector: IdSelector,
+}
+
+#[derive(clap::Args, Debug)]
+struct LinkArgs {
+    /// Where video links point to: youtube, youtube-music, invidious:<host>,
+    /// piped:<host>, none or template:<pattern> with an {id} placeholder
+    #[arg(long, global = true, value_name = "STYLE", default_value = "youtube")]
+    link_style: LinkStyle,
+
+    /// Open video links in the context of this playlist
+    #[arg(long, global = true, value_name = "ID")]
+    playlist_id: Option<String>,
+}
+
+impl GlobalArgs {
+    fn report_options(&self) -> ReportOptions {
+        ReportOptions {
+            date_format: self.dates.date_format.clone(),
+            timezone: self.dates.timezone,
+            links: Links {
+                style: self.links.link_style.clone(),
+                playlist: self.links.playlist_id.clone(),
+            },
+        }
+    }
+
+    /// Opens the repository, or `directory` if the command was given one, and
+    /// checks it against the layout.
+    fn open(&self, directory: Option<&Path>) -> Result<History> {
+        let directory = directory.or(self.repo.as_deref()).unwrap_or(Path::new("."));
+        let history = History::open(directory)?.with_layout(self.layout.layout());
+        history.validate_layout()?;
+        Ok(history)
+    }
+}
+
+impl LayoutArgs {
+    fn layout(&self) -> Layout {
+        Layout {
+            songs_dir: self.songs_dir.clone(),
+            summary_path: self.summary_path.clone(),
+            song_extension: self.song_extension.trim_start_matches('.').to_string(),
+            id_selector: self.id_selector.selector().cloned(),
         }
+    }
+}
+
+fn main() -> ExitCode {
+    let (mut args, matches) = parse_args();
 
-        if added.len() + deleted.len() == 0 {
-            continue;
+    let result = configure(&mut args, &matches).and_then(|()| run(&args));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
         }
+    }
+}
 
-        writeln!(writer, "## {}", format_time(&commit.time()))?;
-        for video in added {
-            writeln!(writer, "Added {}  ", format_video(&video))?;
+/// Parses the arguments, rejecting the top-level report arguments when a
+/// subcommand is given since they would be ignored.
+fn parse_args() -> (Args, ArgMatches) {
+    let mut command = Args::command();
+    let matches = command.get_matches_mut();
+    if let Some((name, _)) = matches.subcommand() {
+        let report = ReportArgs::augment_args(clap::Command::new("report"));
+        for arg in report.get_arguments() {
+            if matches.value_source(arg.get_id().as_str()) != Some(ValueSource::CommandLine) {
+                continue;
+            }
+            let arg = match arg.get_long() {
+                Some(long) => format!("--{}", long),
+                None => format!("<{}>", arg.get_id().as_str().to_uppercase()),
+            };
+            command
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("{} cannot be used with the {} subcommand", arg, name),
+                )
+                .exit();
         }
-        for video in deleted {
-            writeln!(writer, "Removed {}  ", format_video(&video))?;
+    }
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    (args, matches)
+}
+
+/// Fills in the options not given on the command line from the config file.
+fn configure(args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let directory = match &args.command {
+        None => args.report.directory.as_deref(),
+        Some(Command::Report(report)) => report.directory.as_deref(),
+        _ => None,
+    };
+    let directory = directory
+        .or(args.global.repo.as_deref())
+        .unwrap_or(Path::new("."));
+    let config = match &args.global.config {
+        Some(path) => Config::load(path)?,
+        None => match Config::find(directory)? {
+            Some(config) => config,
+            None => return Ok(()),
+        },
+    };
+
+    let submatches = matches.subcommand().map(|(_, submatches)| submatches);
+    let unset = |id: &str| {
+        [Some(matches), submatches]
+            .into_iter()
+            .flatten()
+            .all(|matches| matches.value_source(id) != Some(ValueSource::CommandLine))
+    };
+    fn set<T>(field: &mut T, value: Option<T>, unset: bool) {
+        if let (Some(value), true) = (value, unset) {
+            *field = value;
         }
     }
 
-    println!("Wrote to output.txt");
+    let dates = &mut args.global.dates;
+    set(
+        &mut dates.date_format,
+        config.date_format,
+        unset("date_format"),
+    );
+    set(&mut dates.timezone, config.timezone, unset("timezone"));
+    set(
+        &mut dates.time_source,
+        config.time_source,
+        unset("time_source"),
+    );
+    let layout = &mut args.global.layout;
+    set(&mut layout.songs_dir, config.songs_dir, unset("songs_dir"));
+    set(
+        &mut layout.summary_path,
+        config.summary_path,
+        unset("summary_path"),
+    );
+    set(
+        &mut layout.song_extension,
+        config.song_extension,
+        unset("song_extension"),
+    );
+    set(
+        &mut layout.id_selector,
+        config.id_selector,
+        unset("id_selector"),
+    );
+    let links = &mut args.global.links;
+    set(
+        &mut links.link_style,
+        config.link_style,
+        unset("link_style"),
+    );
+    set(
+        &mut links.playlist_id,
+        config.playlist_id.map(Some),
+        unset("playlist_id"),
+    );
+
+    // The other commands write to stdout unless told otherwise.
+    let (format, output) = match &mut args.command {
+        None => (&mut args.report.format, Some(&mut args.report.output)),
+        Some(Command::Report(report)) => (&mut report.format, Some(&mut report.output)),
+        Some(Command::Snapshot(snapshot)) => (&mut snapshot.format, None),
+        Some(Command::Diff(diff)) => (&mut diff.format, None),
+        Some(Command::Show(show)) => (&mut show.format, None),
+        // Statistics have formats of their own.
+        Some(Command::Stats(_)) | Some(Command::Check) | Some(Command::ExportSqlite(_)) => {
+            return Ok(())
+        }
+    };
+    set(format, config.format, unset("format"));
+    if let Some(output) = output {
+        set(output, config.output, unset("output"));
+    }
     Ok(())
 }
 
-fn format_time(time: &Time) -> String {
-    let (offset, sign) = match time.offset_minutes() {
-        n if n < 0 => (-n, '-'),
-        n => (n, '+'),
+fn run(args: &Args) -> Result<()> {
+    let global = &args.global;
+    match &args.command {
+        None => run_report(global, &args.report),
+        Some(Command::Report(args)) => run_report(global, args),
+        Some(Command::Snapshot(args)) => run_snapshot(global, args),
+        Some(Command::Diff(args)) => run_diff(global, args),
+        Some(Command::Show(args)) => run_show(global, args),
+        Some(Command::Stats(args)) => run_stats(global, args),
+        Some(Command::Check) => run_check(global),
+        Some(Command::ExportSqlite(args)) => run_export_sqlite(global, args),
+    }
+}
+
+fn run_report(global: &GlobalArgs, args: &ReportArgs) -> Result<()> {
+    let history = global.open(args.directory.as_deref())?;
+    let time_source = global.dates.time_source;
+    let mut options = Options {
+        include_initial: args.include_initial,
+        full_history: args.full_history,
+        from: args
+            .from
+            .as_deref()
+            .map(|rev| history.resolve(rev))
+            .transpose()?,
+        to: args
+            .to
+            .as_deref()
+            .map(|rev| history.resolve(rev))
+            .transpose()?,
+        since: args
+            .since
+            .as_deref()
+            .map(|date| date_arg(date, parse_date))
+            .transpose()?,
+        until: args
+            .until
+            .as_deref()
+            .map(|date| date_arg(date, parse_date_end))
+            .transpose()?,
+        time_source,
+        modifications: args.modifications,
+        watch_fields: args.watch_fields.clone(),
+        availability: args.availability,
     };
-    let (hours, minutes) = (offset / 60, offset % 60);
-    let ts = time::Timespec::new(time.seconds() + (time.offset_minutes() as i64) * 60, 0);
-    let time = time::at(ts);
-
-    format!(
-        "{} {}{:02}{:02}",
-        time.strftime("%a %b %e %T %Y").unwrap(),
-        sign,
-        hours,
-        minutes
-    )
-}
-
-fn get_current_ids(repo: &Repository) -> Result<HashSet<String>, git2::Error> {
-    let obj = repo
-        .head()?
-        .peel_to_tree()?
-        .get_path(Path::new("output/summary.json"))?
-        .to_object(&repo)?
-        .peel_to_blob()?;
-    let mut file_content = String::new();
-    obj.content().read_to_string(&mut file_content).unwrap();
-
-    let mut ids: HashSet<String> = HashSet::new();
-
-    let stream = Deserializer::from_str(&file_content).into_iter::<serde_json::Value>();
-
-    for value in stream {
-        if let Ok(obj) = value {
-            if let Some(items) = obj.get("items") {
-                if let Some(items_array) = items.as_array() {
-                    for item in items_array {
-                        let id = item["id"].as_str().unwrap();
-                        ids.insert(id.to_string());
-                    }
-                }
+    let report_options = global.report_options();
+
+    let rules = Rules::new(
+        &options,
+        args.to.as_deref(),
+        history.layout(),
+        &report_options,
+    );
+    let checkpoint_path = Checkpoint::path_for(&args.output);
+    let mut overwrite = args.force;
+    let mut resume = None;
+    if args.incremental {
+        if args.output == Path::new("-") || !args.format.can_append() {
+            return Err(Error::InvalidArgument(
+                "--incremental needs an output file in markdown, jsonl, csv or tsv".to_string(),
+            ));
+        }
+        if let Some(checkpoint) = Checkpoint::load(&checkpoint_path)? {
+            // The report was written by an earlier run, so it is ours to replace.
+            overwrite = true;
+            let tip = history.tip(&options)?;
+            if checkpoint.format != args.format || checkpoint.rules != rules {
+                eprintln!(
+                    "{} was written with other options, rebuilding the report",
+                    checkpoint_path.display()
+                );
+            } else if args.output.exists()
+                && history.is_ancestor(checkpoint.commit, tip).unwrap_or(false)
+            {
+                resume = Some(checkpoint);
+            } else {
+                eprintln!(
+                    "{} does not match the history anymore, rebuilding the report",
+                    checkpoint_path.display()
+                );
             }
         }
     }
+    if let Some(checkpoint) = &resume {
+        options.from = Some(checkpoint.commit);
+    }
+
+    let resuming = resume.is_some();
+    let mut events = match resume {
+        Some(checkpoint) => history.resume(&options, checkpoint.state)?,
+        None => history.events(&options)?,
+    };
+    let mut output = Output::open(&args.output, overwrite)?;
+    let mut report = match resuming {
+        true => {
+            io::copy(&mut File::open(&args.output)?, &mut output)?;
+            ReportWriter::append(BufWriter::new(output), args.format, report_options)
+        }
+        false => ReportWriter::new(BufWriter::new(output), args.format, report_options)?,
+    };
+
+    for event in events.by_ref() {
+        report.write_event(&event?)?;
+    }
+    if args.availability {
+        report.write_unavailable(&history.unavailable_videos(events.tip(), time_source)?)?;
+    }
+
+    finish(report.finish()?)?;
+
+    if args.incremental {
+        let checkpoint = Checkpoint {
+            commit: events.tip(),
+            format: args.format,
+            rules,
+            state: events.state().clone(),
+        };
+        checkpoint.save(&checkpoint_path)?;
+    }
+    Ok(())
+}
+
+fn run_snapshot(global: &GlobalArgs, args: &SnapshotArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let time_source = global.dates.time_source;
+    let commit = history.resolve_point(&args.at, time_source)?;
+    let snapshot = history.snapshot(commit, time_source)?;
+
+    let output = Output::open(&args.output, args.force)?;
+    let writer = report::write_snapshot(
+        BufWriter::new(output),
+        args.format,
+        &global.report_options(),
+        &snapshot,
+    )?;
+    finish(writer)
+}
+
+fn run_diff(global: &GlobalArgs, args: &DiffArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let time_source = global.dates.time_source;
+    let from = history.find_point(&args.from, time_source)?;
+    let to = history.resolve_point(&args.to, time_source)?;
+    let comparison = history.compare(from, to, time_source)?;
+
+    let output = Output::open(&args.output, args.force)?;
+    let writer = report::write_comparison(
+        BufWriter::new(output),
+        args.format,
+        &global.report_options(),
+        &comparison,
+    )?;
+    finish(writer)
+}
+
+fn run_show(global: &GlobalArgs, args: &ShowArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let timeline = history.timeline(&args.video_id, global.dates.time_source)?;
+    if timeline.events.is_empty() {
+        return Err(Error::InvalidArgument(format!(
+            "{} was never in the playlist",
+            args.video_id
+        )));
+    }
+
+    let output = Output::open(&args.output, args.force)?;
+    let writer = report::write_timeline(
+        BufWriter::new(output),
+        args.format,
+        &global.report_options(),
+        &timeline,
+        now(),
+    )?;
+    finish(writer)
+}
+
+fn run_stats(global: &GlobalArgs, args: &StatsArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let stats = history.stats(global.dates.time_source, global.dates.timezone, now())?;
+
+    let output = Output::open(&args.output, args.force)?;
+    let writer = report::write_stats(
+        BufWriter::new(output),
+        args.format,
+        &global.report_options(),
+        &stats,
+    )?;
+    finish(writer)
+}
+
+fn run_check(global: &GlobalArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let repo = history.repository();
+    let layout = history.layout();
+    let head = repo.head()?.peel_to_commit()?;
+
+    let summary = read_summary(repo, &head, &layout.summary_path)?;
+    let songs = song_ids(repo, &head, layout)?;
+    let selector = match &layout.id_selector {
+        Some(selector) => selector.clone(),
+        None => detect_selector(&summary, &songs),
+    };
+    let playlist = read_playlist(
+        repo,
+        &head,
+        &Layout {
+            id_selector: Some(selector.clone()),
+            ..layout.clone()
+        },
+    )?;
+    if !songs.is_empty() && !playlist.iter().any(|id| songs.contains(id)) {
+        return Err(Error::SummaryInvalid(format!(
+            "none of the ids at {} in {} has a song file",
+            selector,
+            layout.summary_path.display()
+        )));
+    }
+
+    println!(
+        "{}: {} song files, {} videos in {} at {}",
+        head.id(),
+        songs.len(),
+        playlist.len(),
+        layout.summary_path.display(),
+        selector
+    );
+    for id in playlist.iter().filter(|id| !songs.contains(*id)) {
+        eprintln!("warning: {} is in the summary but has no song file", id);
+    }
+    let mut extra: Vec<&String> = songs.iter().filter(|id| !playlist.contains(id)).collect();
+    extra.sort();
+    for id in extra {
+        eprintln!("warning: {} has a song file but is not in the summary", id);
+    }
+    Ok(())
+}
+
+fn run_export_sqlite(global: &GlobalArgs, args: &ExportArgs) -> Result<()> {
+    let history = global.open(None)?;
+    let export = history.export_sqlite(&args.database, global.dates.time_source)?;
+    if export.rebuilt {
+        eprintln!(
+            "{} did not match the history anymore and was rebuilt",
+            args.database.display()
+        );
+    }
+    println!(
+        "Exported {} commits and {} events to {}",
+        export.commits,
+        export.events,
+        args.database.display()
+    );
+    Ok(())
+}
+
+/// Moves a finished report into place.
+fn finish(writer: BufWriter<Output>) -> Result<()> {
+    let output = writer.into_inner().map_err(|e| e.into_error())?;
+    if let Some(dest) = output.finish()? {
+        println!("Wrote to {}", dest.display());
+    }
+    Ok(())
+}
 
-    Ok(ids)
+/// The current time in seconds since the epoch.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
 }
 
-fn format_video(video: &str) -> String {
-    format!("[{}](https://youtu.be/{})", video, video)
+fn date_arg(s: &str, parse: fn(&str) -> Option<i64>) -> Result<i64> {
+    parse(s).ok_or_else(|| Error::InvalidArgument(format!("invalid date: {}", s)))
 }
@@ -0,0 +1,114 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{Error, Result};
+
+/// Report destination: either stdout or a temporary file that is moved into
+/// place by [`Output::finish`], so an interrupted run never leaves a partial
+/// report behind.
+pub enum Output {
+    Stdout(io::Stdout),
+    File(TempFile),
+}
+
+pub struct TempFile {
+    file: Option<File>,
+    temp: PathBuf,
+    dest: PathBuf,
+    overwrite: bool,
+}
+
+impl Output {
+    pub fn open(dest: &Path, overwrite: bool) -> Result<Output> {
+        if dest == Path::new("-") {
+            return Ok(Output::Stdout(io::stdout()));
+        }
+        if !overwrite && dest.exists() {
+            return Err(Error::OutputExists(dest.to_path_buf()));
+        }
+        let name = dest
+            .file_name()
+            .unwrap_or(dest.as_os_str())
+            .to_string_lossy();
+        let temp = dest.with_file_name(format!(".{}.{}.tmp", name, process::id()));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        Ok(Output::File(TempFile {
+            file: Some(file),
+            temp,
+            dest: dest.to_path_buf(),
+            overwrite,
+        }))
+    }
+
+    /// Flushes the report and returns the path it was written to, if any.
+    pub fn finish(self) -> Result<Option<PathBuf>> {
+        match self {
+            Output::Stdout(mut stdout) => {
+                stdout.flush()?;
+                Ok(None)
+            }
+            Output::File(mut temp) => {
+                if let Some(file) = temp.file.take() {
+                    file.sync_all()?;
+                }
+                if temp.overwrite {
+                    fs::rename(&temp.temp, &temp.dest)?;
+                } else {
+                    // Linking fails if the destination appeared in the
+                    // meantime, keeping the create_new guarantee.
+                    match fs::hard_link(&temp.temp, &temp.dest) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                            return Err(Error::OutputExists(temp.dest.clone()));
+                        }
+                        // Some filesystems have no hard links; renaming after
+                        // another check is the best they allow.
+                        Err(e)
+                            if matches!(
+                                e.kind(),
+                                io::ErrorKind::Unsupported | io::ErrorKind::PermissionDenied
+                            ) =>
+                        {
+                            if temp.dest.exists() {
+                                return Err(Error::OutputExists(temp.dest.clone()));
+                            }
+                            fs::rename(&temp.temp, &temp.dest)?;
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Ok(Some(temp.dest.clone()))
+            }
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        self.file.take();
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File(temp) => temp.file.as_mut().unwrap().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File(temp) => temp.file.as_mut().unwrap().flush(),
+        }
+    }
+}
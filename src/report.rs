@@ -0,0 +1,978 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use clap::ValueEnum;
+use git2::Oid;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    describe_video,
+    feed::{self, FeedEntry},
+    format_span, format_video, html, Availability, Comparison, DateFormat, EventKind, FieldChange,
+    Growth, HistoryEvent, Links, Snapshot, SnapshotEntry, Stats, Timeline, Timezone,
+    UnavailableVideo,
+};
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// Markdown with a section per commit
+    Markdown,
+    /// A single JSON array of events
+    Json,
+    /// One JSON event per line
+    Jsonl,
+    /// Comma-separated values with a row per event
+    Csv,
+    /// Tab-separated values with a row per event
+    Tsv,
+    /// A standalone HTML page with a section per day
+    Html,
+    /// An Atom feed with an entry per commit
+    Atom,
+    /// An RSS feed with an item per commit
+    Rss,
+}
+
+impl Format {
+    /// Whether reports in this format can be extended by
+    /// [`ReportWriter::append`].
+    pub fn can_append(self) -> bool {
+        matches!(
+            self,
+            Format::Markdown | Format::Jsonl | Format::Csv | Format::Tsv
+        )
+    }
+
+    /// The field separator of [`Format::Csv`] and [`Format::Tsv`].
+    fn delimiter(self) -> char {
+        match self {
+            Format::Tsv => '\t',
+            _ => ',',
+        }
+    }
+}
+
+/// How [`write_stats`] prints statistics.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// Aligned plain-text tables
+    #[default]
+    Table,
+    /// A single JSON object
+    Json,
+}
+
+/// Presentation settings shared by the report formats.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportOptions {
+    pub date_format: DateFormat,
+    pub timezone: Timezone,
+    pub links: Links,
+}
+
+/// Writes a stream of [`HistoryEvent`]s in one of the report formats.
+pub struct ReportWriter<W: Write> {
+    writer: W,
+    format: Format,
+    options: ReportOptions,
+    last_commit: Option<Oid>,
+    /// The day of the open HTML section.
+    last_day: Option<String>,
+    events: Vec<Value>,
+    /// Events kept for the feed formats, which are written newest first.
+    feed_events: Vec<HistoryEvent>,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Added => "added",
+            EventKind::Removed => "removed",
+            EventKind::ReAdded => "re-added",
+            EventKind::Modified => "modified",
+            EventKind::BecameUnavailable => "became-unavailable",
+            EventKind::BecameAvailable => "became-available",
+        }
+    }
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            EventKind::Added => "Added",
+            EventKind::Removed => "Removed",
+            EventKind::ReAdded => "Re-added",
+            EventKind::Modified => "Modified",
+            EventKind::BecameUnavailable => "Became unavailable",
+            EventKind::BecameAvailable => "Became available",
+        }
+    }
+}
+
+impl<W: Write> ReportWriter<W> {
+    pub fn new(mut writer: W, format: Format, options: ReportOptions) -> io::Result<Self> {
+        match format {
+            Format::Markdown => writeln!(writer, "# songs-history")?,
+            Format::Csv | Format::Tsv => {
+                write_record(&mut writer, format.delimiter(), &EVENT_COLUMNS)?
+            }
+            Format::Html => html::write_header(&mut writer, "songs-history")?,
+            Format::Json | Format::Jsonl | Format::Atom | Format::Rss => {}
+        }
+        Ok(ReportWriter::append(writer, format, options))
+    }
+
+    /// Continues a report that has already been started in `writer`.
+    pub fn append(writer: W, format: Format, options: ReportOptions) -> Self {
+        ReportWriter {
+            writer,
+            format,
+            options,
+            last_commit: None,
+            last_day: None,
+            events: Vec::new(),
+            feed_events: Vec::new(),
+        }
+    }
+
+    pub fn write_event(&mut self, event: &HistoryEvent) -> io::Result<()> {
+        match self.format {
+            Format::Markdown => {
+                if self.last_commit != Some(event.commit) {
+                    let time = self
+                        .options
+                        .date_format
+                        .format(&event.time, self.options.timezone);
+                    write!(self.writer, "## {}", time)?;
+                    if event.initial {
+                        write!(self.writer, " (initial import)")?;
+                    }
+                    writeln!(self.writer)?;
+                    self.last_commit = Some(event.commit);
+                }
+                writeln!(
+                    self.writer,
+                    "{} {}{}  ",
+                    event.kind.label(),
+                    describe_video(&event.video_id, &event.info, &self.options.links),
+                    event_details(event)
+                )
+            }
+            Format::Html => {
+                let day = day(&event.time, &self.options);
+                if self.last_day.as_ref() != Some(&day) {
+                    self.close_day()?;
+                    writeln!(
+                        self.writer,
+                        "<details open>\n<summary>{}</summary>\n<ul>",
+                        html::escape(&day)
+                    )?;
+                    self.last_day = Some(day);
+                }
+                let time = self
+                    .options
+                    .date_format
+                    .format(&event.time, self.options.timezone);
+                html::write_video(
+                    &mut self.writer,
+                    event.kind.as_str(),
+                    Some(event.kind),
+                    &event.video_id,
+                    &event.info,
+                    &self.options.links,
+                    &format!("{}{}", time, event_details(event)),
+                )
+            }
+            Format::Json => {
+                self.events.push(event_json(event, &self.options.links));
+                Ok(())
+            }
+            Format::Jsonl => writeln!(self.writer, "{}", event_json(event, &self.options.links)),
+            Format::Atom | Format::Rss => {
+                self.feed_events.push(event.clone());
+                Ok(())
+            }
+            Format::Csv | Format::Tsv => {
+                let record = event_record(event, &self.options);
+                write_record(&mut self.writer, self.format.delimiter(), &record)
+            }
+        }
+    }
+
+    /// Writes a closing section listing videos that are unavailable now.
+    /// Feeds leave it out.
+    pub fn write_unavailable(&mut self, videos: &[UnavailableVideo]) -> io::Result<()> {
+        if matches!(self.format, Format::Atom | Format::Rss) {
+            return Ok(());
+        }
+        if matches!(self.format, Format::Csv | Format::Tsv) {
+            for video in videos {
+                let since = video.since.map(|time| iso_time(&time, &self.options));
+                let url = self.options.links.url(&video.video_id);
+                let record = [
+                    since.as_deref().unwrap_or(""),
+                    "unavailable",
+                    &video.video_id,
+                    url.as_deref().unwrap_or(""),
+                    "",
+                    video.last_known.title.as_deref().unwrap_or(""),
+                    video.last_known.channel.as_deref().unwrap_or(""),
+                ];
+                write_record(&mut self.writer, self.format.delimiter(), &record)?;
+            }
+            return Ok(());
+        }
+        if self.format == Format::Html {
+            self.close_day()?;
+            writeln!(self.writer, "<h2>Currently unavailable</h2>\n<ul>")?;
+            for video in videos {
+                let since = video.since.map(|since| {
+                    let since = self
+                        .options
+                        .date_format
+                        .format(&since, self.options.timezone);
+                    format!(" since {}", since)
+                });
+                html::write_video(
+                    &mut self.writer,
+                    "unavailable",
+                    None,
+                    &video.video_id,
+                    &video.last_known,
+                    &self.options.links,
+                    &format!(
+                        "{}{}",
+                        video.availability.as_str(),
+                        since.unwrap_or_default()
+                    ),
+                )?;
+            }
+            return writeln!(self.writer, "</ul>");
+        }
+        if self.format != Format::Markdown {
+            for video in videos {
+                let since = video.since.map(|time| {
+                    json!({
+                        "seconds": time.seconds(),
+                        "offset_minutes": time.offset_minutes(),
+                    })
+                });
+                let entry = json!({
+                    "kind": "unavailable",
+                    "video_id": video.video_id,
+                    "availability": video.availability.as_str(),
+                    "url": self.options.links.url(&video.video_id),
+                    "title": video.last_known.title,
+                    "channel": video.last_known.channel,
+                    "since": since,
+                });
+                match self.format {
+                    Format::Jsonl => writeln!(self.writer, "{}", entry)?,
+                    _ => self.events.push(entry),
+                }
+            }
+            return Ok(());
+        }
+
+        writeln!(self.writer, "## Currently unavailable")?;
+        for video in videos {
+            write!(
+                self.writer,
+                "- {} ({}",
+                describe_video(&video.video_id, &video.last_known, &self.options.links),
+                video.availability.as_str()
+            )?;
+            if let Some(since) = video.since {
+                let since = self
+                    .options
+                    .date_format
+                    .format(&since, self.options.timezone);
+                write!(self.writer, " since {}", since)?;
+            }
+            writeln!(self.writer, ")")?;
+        }
+        Ok(())
+    }
+
+    /// Ends the HTML section of the previous day, if one is open.
+    fn close_day(&mut self) -> io::Result<()> {
+        if self.last_day.take().is_some() {
+            writeln!(self.writer, "</ul>\n</details>")?;
+        }
+        Ok(())
+    }
+
+    /// Writes anything buffered by the format and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.format {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut self.writer, &self.events)?;
+                writeln!(self.writer)?;
+            }
+            Format::Html => {
+                self.close_day()?;
+                html::write_footer(&mut self.writer)?;
+            }
+            Format::Atom | Format::Rss => {
+                let entries = event_entries(&self.feed_events, &self.options);
+                write_feed(
+                    &mut self.writer,
+                    self.format,
+                    "songs-history",
+                    &entries,
+                    &self.options,
+                )?;
+            }
+            _ => {}
+        }
+        Ok(self.writer)
+    }
+}
+
+/// Groups events into a feed entry per commit, titled with what changed.
+fn event_entries(events: &[HistoryEvent], options: &ReportOptions) -> Vec<FeedEntry> {
+    let mut entries = Vec::new();
+    for commit in events.chunk_by(|a, b| a.commit == b.commit) {
+        let mut counts: Vec<(EventKind, usize)> = Vec::new();
+        for event in commit {
+            match counts.iter_mut().find(|(kind, _)| *kind == event.kind) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((event.kind, 1)),
+            }
+        }
+        let title: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind.label().to_lowercase()))
+            .collect();
+        let videos = commit.iter().map(|event| {
+            let description = html::describe_video(&event.video_id, &event.info, &options.links);
+            let details = html::escape(&event_details(event)).into_owned();
+            format!("{} {}{}", event.kind.label(), description, details)
+        });
+        entries.push(FeedEntry {
+            commit: commit[0].commit,
+            time: commit[0].time,
+            title: title.join(", "),
+            content: html_list(videos),
+        });
+    }
+    entries
+}
+
+fn html_list(items: impl Iterator<Item = String>) -> String {
+    let items: String = items.map(|item| format!("<li>{}</li>", item)).collect();
+    format!("<ul>{}</ul>", items)
+}
+
+fn write_feed<W: Write>(
+    writer: &mut W,
+    format: Format,
+    title: &str,
+    entries: &[FeedEntry],
+    options: &ReportOptions,
+) -> io::Result<()> {
+    match format {
+        Format::Rss => feed::write_rss(writer, title, entries, options),
+        _ => feed::write_atom(writer, title, entries, options),
+    }
+}
+
+/// The day an event happened on, as HTML reports head their sections.
+fn day(time: &git2::Time, options: &ReportOptions) -> String {
+    DateFormat::Custom("%A, %B %-d, %Y".to_string()).format(time, options.timezone)
+}
+
+/// What is said about an event after the video, such as the fields that
+/// changed.
+fn event_details(event: &HistoryEvent) -> String {
+    let mut details = String::new();
+    if let Some(removed_at) = event.removed_at {
+        let days = (event.time.seconds() - removed_at.seconds()) / (24 * 60 * 60);
+        match days {
+            1 => details.push_str(" after 1 day"),
+            _ => details.push_str(&format!(" after {} days", days)),
+        }
+    }
+    if let Some(availability) = event.availability {
+        details.push_str(&format!(" ({})", availability.as_str()));
+    }
+    if !event.changes.is_empty() {
+        let changes: Vec<String> = event.changes.iter().map(FieldChange::describe).collect();
+        details.push_str(&format!(": {}", changes.join("; ")));
+    }
+    details
+}
+
+const EVENT_COLUMNS: [&str; 7] = [
+    "time", "kind", "video_id", "url", "commit", "title", "channel",
+];
+
+fn event_record(event: &HistoryEvent, options: &ReportOptions) -> [String; 7] {
+    [
+        iso_time(&event.time, options),
+        event.kind.as_str().to_string(),
+        event.video_id.clone(),
+        options.links.url(&event.video_id).unwrap_or_default(),
+        event.commit.to_string(),
+        event.info.title.clone().unwrap_or_default(),
+        event.info.channel.clone().unwrap_or_default(),
+    ]
+}
+
+fn iso_time(time: &git2::Time, options: &ReportOptions) -> String {
+    DateFormat::Iso.format(time, options.timezone)
+}
+
+/// Writes one row of a CSV or TSV table, quoting fields as RFC 4180 does.
+fn write_record<W: Write, S: AsRef<str>>(
+    writer: &mut W,
+    delimiter: char,
+    fields: &[S],
+) -> io::Result<()> {
+    let fields: Vec<Cow<str>> = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+            } else {
+                Cow::Borrowed(field)
+            }
+        })
+        .collect();
+    writeln!(writer, "{}", fields.join(&delimiter.to_string()))
+}
+
+fn event_json(event: &HistoryEvent, links: &Links) -> Value {
+    json!({
+        "kind": event.kind.as_str(),
+        "video_id": event.video_id,
+        "commit": event.commit.to_string(),
+        "time": {
+            "seconds": event.time.seconds(),
+            "offset_minutes": event.time.offset_minutes(),
+        },
+        "url": links.url(&event.video_id),
+        "title": event.info.title,
+        "channel": event.info.channel,
+        "duration_seconds": event.info.duration,
+        "availability": event.availability.map(Availability::as_str),
+        "changes": event.changes.iter().map(|change| json!({
+            "field": change.field.to_string(),
+            "old": change.old,
+            "new": change.new,
+        })).collect::<Vec<_>>(),
+        "initial": event.initial,
+        "removed_at": event.removed_at.map(|time| json!({
+            "seconds": time.seconds(),
+            "offset_minutes": time.offset_minutes(),
+        })),
+    })
+}
+
+/// Writes the videos of a [`Snapshot`] in one of the report formats.
+pub fn write_snapshot<W: Write>(
+    mut writer: W,
+    format: Format,
+    options: &ReportOptions,
+    snapshot: &Snapshot,
+) -> io::Result<W> {
+    let time = options.date_format.format(&snapshot.time, options.timezone);
+    match format {
+        Format::Markdown => {
+            writeln!(writer, "# songs-history snapshot")?;
+            writeln!(writer, "## {} ({} videos)", time, snapshot.videos.len())?;
+            for video in &snapshot.videos {
+                writeln!(
+                    writer,
+                    "- {}",
+                    describe_video(&video.video_id, &video.info, &options.links)
+                )?;
+            }
+        }
+        Format::Json => {
+            let snapshot = json!({
+                "commit": snapshot.commit.to_string(),
+                "time": {
+                    "seconds": snapshot.time.seconds(),
+                    "offset_minutes": snapshot.time.offset_minutes(),
+                },
+                "videos": snapshot
+                    .videos
+                    .iter()
+                    .map(|video| entry_json(video, &options.links))
+                    .collect::<Vec<_>>(),
+            });
+            serde_json::to_writer_pretty(&mut writer, &snapshot)?;
+            writeln!(writer)?;
+        }
+        Format::Jsonl => {
+            for video in &snapshot.videos {
+                writeln!(writer, "{}", entry_json(video, &options.links))?;
+            }
+        }
+        Format::Html => {
+            html::write_header(&mut writer, "songs-history snapshot")?;
+            writeln!(
+                writer,
+                "<h2>{} ({} videos)</h2>\n<ul>",
+                html::escape(&time),
+                snapshot.videos.len()
+            )?;
+            for video in &snapshot.videos {
+                html::write_video(
+                    &mut writer,
+                    "video",
+                    None,
+                    &video.video_id,
+                    &video.info,
+                    &options.links,
+                    "",
+                )?;
+            }
+            writeln!(writer, "</ul>")?;
+            html::write_footer(&mut writer)?;
+        }
+        Format::Atom | Format::Rss => {
+            let videos = snapshot
+                .videos
+                .iter()
+                .map(|video| html::describe_video(&video.video_id, &video.info, &options.links));
+            let entry = FeedEntry {
+                commit: snapshot.commit,
+                time: snapshot.time,
+                title: format!("{} videos", snapshot.videos.len()),
+                content: html_list(videos),
+            };
+            write_feed(
+                &mut writer,
+                format,
+                "songs-history snapshot",
+                &[entry],
+                options,
+            )?;
+        }
+        Format::Csv | Format::Tsv => {
+            write_record(&mut writer, format.delimiter(), &ENTRY_COLUMNS)?;
+            for video in &snapshot.videos {
+                write_record(
+                    &mut writer,
+                    format.delimiter(),
+                    &entry_record(video, &options.links),
+                )?;
+            }
+        }
+    }
+    Ok(writer)
+}
+
+/// Writes the net changes of a [`Comparison`] in one of the report formats.
+pub fn write_comparison<W: Write>(
+    mut writer: W,
+    format: Format,
+    options: &ReportOptions,
+    comparison: &Comparison,
+) -> io::Result<W> {
+    let changes = comparison
+        .added
+        .iter()
+        .map(|video| (EventKind::Added, video))
+        .chain(
+            comparison
+                .removed
+                .iter()
+                .map(|video| (EventKind::Removed, video)),
+        );
+    let from = match &comparison.from_time {
+        Some(time) => options.date_format.format(time, options.timezone),
+        None => "the beginning".to_string(),
+    };
+    let to = options
+        .date_format
+        .format(&comparison.to_time, options.timezone);
+    match format {
+        Format::Markdown => {
+            writeln!(writer, "# songs-history diff")?;
+            writeln!(
+                writer,
+                "## {} to {} ({} added, {} removed)",
+                from,
+                to,
+                comparison.added.len(),
+                comparison.removed.len()
+            )?;
+            for (kind, video) in changes {
+                writeln!(
+                    writer,
+                    "{} {}  ",
+                    kind.label(),
+                    describe_video(&video.video_id, &video.info, &options.links)
+                )?;
+            }
+        }
+        Format::Json => {
+            let point = |commit: Oid, time: &git2::Time| {
+                json!({
+                    "commit": commit.to_string(),
+                    "time": {
+                        "seconds": time.seconds(),
+                        "offset_minutes": time.offset_minutes(),
+                    },
+                })
+            };
+            let entries = |videos: &[SnapshotEntry]| -> Vec<Value> {
+                videos
+                    .iter()
+                    .map(|video| entry_json(video, &options.links))
+                    .collect()
+            };
+            let comparison = json!({
+                "from": comparison.from.zip(comparison.from_time).map(|(commit, time)| point(commit, &time)),
+                "to": point(comparison.to, &comparison.to_time),
+                "added": entries(&comparison.added),
+                "removed": entries(&comparison.removed),
+            });
+            serde_json::to_writer_pretty(&mut writer, &comparison)?;
+            writeln!(writer)?;
+        }
+        Format::Jsonl => {
+            for (kind, video) in changes {
+                let mut entry = entry_json(video, &options.links);
+                entry["kind"] = kind.as_str().into();
+                writeln!(writer, "{}", entry)?;
+            }
+        }
+        Format::Html => {
+            html::write_header(&mut writer, "songs-history diff")?;
+            writeln!(
+                writer,
+                "<h2>{} to {} ({} added, {} removed)</h2>\n<ul>",
+                html::escape(&from),
+                html::escape(&to),
+                comparison.added.len(),
+                comparison.removed.len()
+            )?;
+            for (kind, video) in changes {
+                html::write_video(
+                    &mut writer,
+                    kind.as_str(),
+                    Some(kind),
+                    &video.video_id,
+                    &video.info,
+                    &options.links,
+                    "",
+                )?;
+            }
+            writeln!(writer, "</ul>")?;
+            html::write_footer(&mut writer)?;
+        }
+        Format::Atom | Format::Rss => {
+            let videos = changes.map(|(kind, video)| {
+                let description =
+                    html::describe_video(&video.video_id, &video.info, &options.links);
+                format!("{} {}", kind.label(), description)
+            });
+            let entry = FeedEntry {
+                commit: comparison.to,
+                time: comparison.to_time,
+                title: format!(
+                    "{} added, {} removed since {}",
+                    comparison.added.len(),
+                    comparison.removed.len(),
+                    from
+                ),
+                content: html_list(videos),
+            };
+            write_feed(&mut writer, format, "songs-history diff", &[entry], options)?;
+        }
+        Format::Csv | Format::Tsv => {
+            let columns: Vec<&str> = ["kind"].into_iter().chain(ENTRY_COLUMNS).collect();
+            write_record(&mut writer, format.delimiter(), &columns)?;
+            for (kind, video) in changes {
+                let record: Vec<String> = [kind.as_str().to_string()]
+                    .into_iter()
+                    .chain(entry_record(video, &options.links))
+                    .collect();
+                write_record(&mut writer, format.delimiter(), &record)?;
+            }
+        }
+    }
+    Ok(writer)
+}
+
+/// Writes every change to one video and how long it was in the playlist.
+pub fn write_timeline<W: Write>(
+    mut writer: W,
+    format: Format,
+    options: &ReportOptions,
+    timeline: &Timeline,
+    now: i64,
+) -> io::Result<W> {
+    let seconds = timeline.seconds_in_playlist(now);
+    match format {
+        Format::Markdown => {
+            writeln!(
+                writer,
+                "# {}",
+                format_video(&timeline.video_id, &options.links)
+            )?;
+            for event in &timeline.events {
+                let time = options.date_format.format(&event.time, options.timezone);
+                writeln!(
+                    writer,
+                    "- {}: {} {}",
+                    time,
+                    event.kind.label(),
+                    describe_video(&event.video_id, &event.info, &options.links)
+                )?;
+            }
+            writeln!(writer)?;
+            writeln!(writer, "{}", presence(timeline, seconds))?;
+        }
+        Format::Html => {
+            html::write_header(&mut writer, &timeline.video_id)?;
+            writeln!(writer, "<p>{}</p>\n<ul>", presence(timeline, seconds))?;
+            for event in &timeline.events {
+                html::write_video(
+                    &mut writer,
+                    event.kind.as_str(),
+                    Some(event.kind),
+                    &event.video_id,
+                    &event.info,
+                    &options.links,
+                    &options.date_format.format(&event.time, options.timezone),
+                )?;
+            }
+            writeln!(writer, "</ul>")?;
+            html::write_footer(&mut writer)?;
+        }
+        Format::Atom | Format::Rss => {
+            let entries = event_entries(&timeline.events, options);
+            write_feed(&mut writer, format, &timeline.video_id, &entries, options)?;
+        }
+        Format::Json => {
+            let events: Vec<Value> = timeline
+                .events
+                .iter()
+                .map(|event| event_json(event, &options.links))
+                .collect();
+            let timeline = json!({
+                "video_id": timeline.video_id,
+                "url": options.links.url(&timeline.video_id),
+                "events": events,
+                "seconds_in_playlist": seconds,
+                "present": timeline.is_present(),
+            });
+            serde_json::to_writer_pretty(&mut writer, &timeline)?;
+            writeln!(writer)?;
+        }
+        Format::Jsonl => {
+            for event in &timeline.events {
+                writeln!(writer, "{}", event_json(event, &options.links))?;
+            }
+        }
+        Format::Csv | Format::Tsv => {
+            write_record(&mut writer, format.delimiter(), &EVENT_COLUMNS)?;
+            for event in &timeline.events {
+                write_record(
+                    &mut writer,
+                    format.delimiter(),
+                    &event_record(event, options),
+                )?;
+            }
+        }
+    }
+    Ok(writer)
+}
+
+/// Writes the [`Stats`] of a playlist.
+pub fn write_stats<W: Write>(
+    mut writer: W,
+    format: StatsFormat,
+    options: &ReportOptions,
+    stats: &Stats,
+) -> io::Result<W> {
+    let growth = |periods: &[Growth]| -> Vec<Value> {
+        periods
+            .iter()
+            .map(|growth| {
+                json!({
+                    "period": growth.period,
+                    "added": growth.added,
+                    "removed": growth.removed,
+                    "net": growth.net(),
+                })
+            })
+            .collect()
+    };
+    match format {
+        StatsFormat::Table => {
+            writeln!(writer, "Added         {:>6}", stats.added)?;
+            writeln!(writer, "  re-added    {:>6}", stats.re_added)?;
+            writeln!(writer, "Removed       {:>6}", stats.removed)?;
+            writeln!(writer, "Current size  {:>6}", stats.current_size)?;
+            if let Some(lifespan) = stats.average_lifespan {
+                writeln!(
+                    writer,
+                    "Removed videos stayed {} on average",
+                    format_span(lifespan)
+                )?;
+            }
+            for (title, periods) in [
+                ("Growth per year", &stats.per_year),
+                ("Growth per month", &stats.per_month),
+            ] {
+                writeln!(writer, "\n{}", title)?;
+                for growth in periods {
+                    writeln!(
+                        writer,
+                        "{:<8} {:>+6}  ({} added, {} removed)",
+                        growth.period,
+                        growth.net(),
+                        growth.added,
+                        growth.removed
+                    )?;
+                }
+            }
+            writeln!(writer, "\nBusiest days")?;
+            for (day, changes) in &stats.busiest_days {
+                let unit = if *changes == 1 { "change" } else { "changes" };
+                writeln!(writer, "{:<10} {:>6} {}", day, changes, unit)?;
+            }
+            writeln!(writer, "\nLongest in the playlist")?;
+            let rows: Vec<[String; 5]> = stats
+                .longest_surviving
+                .iter()
+                .map(|survivor| {
+                    let info = &survivor.info;
+                    [
+                        format_span(survivor.seconds),
+                        options
+                            .date_format
+                            .format(&survivor.since, options.timezone),
+                        info.title.clone().unwrap_or(survivor.video_id.clone()),
+                        info.channel.clone().unwrap_or_default(),
+                        options.links.url(&survivor.video_id).unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            let mut widths = [0; 5];
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            for row in &rows {
+                let line = row
+                    .iter()
+                    .zip(widths)
+                    .map(|(cell, width)| format!("{:<1$}", cell, width))
+                    .collect::<Vec<_>>()
+                    .join("  ");
+                writeln!(writer, "{}", line.trim_end())?;
+            }
+        }
+        StatsFormat::Json => {
+            let busiest_days: Vec<Value> = stats
+                .busiest_days
+                .iter()
+                .map(|(day, changes)| json!({ "day": day, "changes": changes }))
+                .collect();
+            let longest_surviving: Vec<Value> = stats
+                .longest_surviving
+                .iter()
+                .map(|survivor| {
+                    json!({
+                        "video_id": survivor.video_id,
+                        "url": options.links.url(&survivor.video_id),
+                        "title": survivor.info.title,
+                        "channel": survivor.info.channel,
+                        "since": {
+                            "seconds": survivor.since.seconds(),
+                            "offset_minutes": survivor.since.offset_minutes(),
+                        },
+                        "seconds_in_playlist": survivor.seconds,
+                    })
+                })
+                .collect();
+            let stats = json!({
+                "added": stats.added,
+                "re_added": stats.re_added,
+                "removed": stats.removed,
+                "current_size": stats.current_size,
+                "average_lifespan_seconds": stats.average_lifespan,
+                "per_year": growth(&stats.per_year),
+                "per_month": growth(&stats.per_month),
+                "busiest_days": busiest_days,
+                "longest_surviving": longest_surviving,
+            });
+            serde_json::to_writer_pretty(&mut writer, &stats)?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(writer)
+}
+
+/// How long a video has been in the playlist, and whether it still is.
+fn presence(timeline: &Timeline, seconds: i64) -> String {
+    match timeline.is_present() {
+        true => format!("In the playlist for {} and counting", format_span(seconds)),
+        false => format!("In the playlist for {}", format_span(seconds)),
+    }
+}
+
+fn entry_json(video: &SnapshotEntry, links: &Links) -> Value {
+    json!({
+        "video_id": video.video_id,
+        "url": links.url(&video.video_id),
+        "title": video.info.title,
+        "channel": video.info.channel,
+        "duration_seconds": video.info.duration,
+    })
+}
+
+const ENTRY_COLUMNS: [&str; 5] = ["video_id", "url", "title", "channel", "duration_seconds"];
+
+fn entry_record(video: &SnapshotEntry, links: &Links) -> [String; 5] {
+    [
+        video.video_id.clone(),
+        links.url(&video.video_id).unwrap_or_default(),
+        video.info.title.clone().unwrap_or_default(),
+        video.info.channel.clone().unwrap_or_default(),
+        video
+            .info
+            .duration
+            .map(|duration| duration.to_string())
+            .unwrap_or_default(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(delimiter: char, fields: &[&str]) -> String {
+        let mut buffer = Vec::new();
+        write_record(&mut buffer, delimiter, fields).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn plain_fields_are_not_quoted() {
+        assert_eq!(record(',', &["a", "b c", ""]), "a,b c,\n");
+        assert_eq!(record('\t', &["a,b", "c"]), "a,b\tc\n");
+    }
+
+    #[test]
+    fn quotes_fields_with_special_characters() {
+        assert_eq!(record(',', &["a,b", "c"]), "\"a,b\",c\n");
+        assert_eq!(record('\t', &["a\tb", "c"]), "\"a\tb\"\tc\n");
+        assert_eq!(record(',', &["say \"hi\""]), "\"say \"\"hi\"\"\"\n");
+        assert_eq!(
+            record(',', &["two\nlines", "x\r"]),
+            "\"two\nlines\",\"x\r\"\n"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fmt, str::FromStr};
+
+use serde_json::Value;
+
+/// A path into a JSON document such as `items[].contentDetails.videoId`,
+/// where `[]` steps into every element of an array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Each,
+}
+
+impl Selector {
+    /// Selectors tried when detecting where the video ids are, most specific
+    /// first.
+    pub const CANDIDATES: [&'static str; 3] = [
+        "items[].contentDetails.videoId",
+        "items[].snippet.resourceId.videoId",
+        "items[].id",
+    ];
+
+    /// The last key of the selector, e.g. `videoId`.
+    pub fn name(&self) -> Option<&str> {
+        self.steps.iter().rev().find_map(|step| match step {
+            Step::Key(key) => Some(key.as_str()),
+            Step::Each => None,
+        })
+    }
+
+    /// All values the selector reaches in `json`.
+    pub fn select<'a>(&self, json: &'a Value) -> Vec<&'a Value> {
+        let mut values = vec![json];
+        for step in &self.steps {
+            values = match step {
+                Step::Key(key) => values.iter().filter_map(|value| value.get(key)).collect(),
+                Step::Each => values
+                    .iter()
+                    .filter_map(|value| value.as_array())
+                    .flatten()
+                    .collect(),
+            };
+        }
+        values
+    }
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for segment in s.split('.') {
+            let key = segment.trim_end_matches("[]");
+            if key.is_empty() {
+                return Err(format!("invalid selector: {}", s));
+            }
+            steps.push(Step::Key(key.to_string()));
+            for _ in 0..(segment.len() - key.len()) / 2 {
+                steps.push(Step::Each);
+            }
+        }
+        Ok(Selector { steps })
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            match step {
+                Step::Key(key) if i > 0 => write!(f, ".{}", key)?,
+                Step::Key(key) => write!(f, "{}", key)?,
+                Step::Each => write!(f, "[]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`Selector`] given by the user, or `auto` to detect one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum IdSelector {
+    #[default]
+    Auto,
+    Selector(Selector),
+}
+
+impl IdSelector {
+    pub fn selector(&self) -> Option<&Selector> {
+        match self {
+            IdSelector::Auto => None,
+            IdSelector::Selector(selector) => Some(selector),
+        }
+    }
+}
+
+impl FromStr for IdSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(IdSelector::Auto),
+            selector => selector.parse().map(IdSelector::Selector),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_selectors() {
+        for s in ["id", "items[].contentDetails.videoId", "a[][].b", "a.b[]"] {
+            assert_eq!(s.parse::<Selector>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn nested_arrays_step_into_every_element() {
+        let selector: Selector = "a[][].b".parse().unwrap();
+        let json = json!({ "a": [[{ "b": 1 }, { "b": 2 }], [{ "b": 3 }]] });
+        assert_eq!(selector.select(&json), [&json!(1), &json!(2), &json!(3)]);
+        assert_eq!(selector.name(), Some("b"));
+    }
+
+    #[test]
+    fn rejects_empty_keys() {
+        for s in ["", ".a", "a.", "a..b", "[]", "a.[]"] {
+            assert!(s.parse::<Selector>().is_err(), "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn parses_id_selectors() {
+        assert_eq!("auto".parse::<IdSelector>().unwrap(), IdSelector::Auto);
+        let selector = "items[].id".parse::<IdSelector>().unwrap();
+        assert_eq!(selector.selector().unwrap().to_string(), "items[].id");
+    }
+}
@@ -0,0 +1,94 @@
+use git2::{Oid, Time};
+
+use crate::{
+    parse_date_end, read_playlist, song_ids, Error, History, Result, TimeSource, VideoInfo,
+};
+
+/// The videos in the playlist as of one commit.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub commit: Oid,
+    pub time: Time,
+    pub videos: Vec<SnapshotEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SnapshotEntry {
+    pub video_id: String,
+    pub info: VideoInfo,
+}
+
+impl History {
+    /// Resolves a revision, or failing that a date, to a commit. A date
+    /// resolves to the last commit made at or before it, and a plain
+    /// `YYYY-MM-DD` to the last commit made by the end of that day.
+    pub fn resolve_point(&self, spec: &str, time_source: TimeSource) -> Result<Oid> {
+        self.find_point(spec, time_source)?
+            .ok_or_else(|| Error::InvalidArgument(format!("no commit at or before {}", spec)))
+    }
+
+    /// Like [`History::resolve_point`], but a date before the first commit
+    /// resolves to `None`.
+    pub fn find_point(&self, spec: &str, time_source: TimeSource) -> Result<Option<Oid>> {
+        if let Ok(oid) = self.resolve(spec) {
+            return Ok(Some(oid));
+        }
+        let Some(time) = parse_date_end(spec) else {
+            return Err(Error::InvalidArgument(format!(
+                "{} is neither a revision nor a date",
+                spec
+            )));
+        };
+        self.commit_at(time, time_source)
+    }
+
+    /// The last commit on HEAD made at or before `time`.
+    pub fn commit_at(&self, time: i64, time_source: TimeSource) -> Result<Option<Oid>> {
+        let repo = self.repository();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        let mut best: Option<(i64, Oid)> = None;
+        for oid in revwalk {
+            let oid = oid?;
+            let seconds = time_source.time(&repo.find_commit(oid)?).seconds();
+            if seconds <= time && !matches!(best, Some((best, _)) if best >= seconds) {
+                best = Some((seconds, oid));
+            }
+        }
+        Ok(best.map(|(_, oid)| oid))
+    }
+
+    /// Lists the videos in the playlist at `commit`, in playlist order when
+    /// the commit has a summary and by id otherwise.
+    pub fn snapshot(&self, commit: Oid, time_source: TimeSource) -> Result<Snapshot> {
+        let repo = self.repository();
+        let layout = self.layout();
+        let commit = repo.find_commit(commit)?;
+        let tree = commit.tree()?;
+
+        let ids = match tree.get_path(&layout.summary_path) {
+            Ok(_) => read_playlist(repo, &commit, layout)?,
+            Err(_) => {
+                let mut ids: Vec<String> = song_ids(repo, &commit, layout)?.into_iter().collect();
+                ids.sort();
+                ids
+            }
+        };
+        let videos = ids
+            .into_iter()
+            .map(|video_id| {
+                let info = tree
+                    .get_path(&layout.song_path(&video_id))
+                    .map(|entry| VideoInfo::from_blob(repo, entry.id()))
+                    .unwrap_or_default();
+                SnapshotEntry { video_id, info }
+            })
+            .collect();
+
+        Ok(Snapshot {
+            commit: commit.id(),
+            time: time_source.time(&commit),
+            videos,
+        })
+    }
+}
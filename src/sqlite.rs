@@ -0,0 +1,175 @@
+use std::{collections::HashMap, path::Path};
+
+use git2::{Oid, Time};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::{History, Options, Result, State, TimeSource, VideoInfo};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS commits (
+    oid TEXT PRIMARY KEY,
+    time INTEGER NOT NULL,
+    offset_minutes INTEGER NOT NULL,
+    message TEXT
+);
+CREATE TABLE IF NOT EXISTS videos (
+    id TEXT PRIMARY KEY,
+    title TEXT,
+    channel TEXT
+);
+CREATE TABLE IF NOT EXISTS events (
+    commit_oid TEXT NOT NULL REFERENCES commits (oid),
+    video_id TEXT NOT NULL REFERENCES videos (id),
+    kind TEXT NOT NULL,
+    PRIMARY KEY (commit_oid, video_id, kind)
+);
+CREATE INDEX IF NOT EXISTS events_video ON events (video_id);
+";
+
+/// What [`History::export_sqlite`] wrote.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Export {
+    pub commits: usize,
+    pub events: usize,
+    /// Whether the database was emptied first because the history it was
+    /// exported from was rewritten.
+    pub rebuilt: bool,
+}
+
+impl History {
+    /// Writes the commits, the videos with their last known details and
+    /// every add, removal and availability change to a SQLite database.
+    ///
+    /// Only commits made since the last export are walked, unless HEAD no
+    /// longer descends from it.
+    pub fn export_sqlite(&self, path: &Path, time_source: TimeSource) -> Result<Export> {
+        let mut connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        let transaction = connection.transaction()?;
+        let tip = self.repository().head()?.peel_to_commit()?.id();
+        let mut export = Export::default();
+
+        let last: Option<String> = transaction
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'last_commit'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let last = match last.and_then(|last| Oid::from_str(&last).ok()) {
+            Some(last) if self.is_ancestor(last, tip).unwrap_or(false) => Some(last),
+            Some(_) => {
+                transaction.execute_batch(
+                    "DELETE FROM events; DELETE FROM videos; DELETE FROM commits;",
+                )?;
+                export.rebuilt = true;
+                None
+            }
+            None => None,
+        };
+
+        let mut revwalk = self.repository().revwalk()?;
+        revwalk.push(tip)?;
+        if let Some(last) = last {
+            revwalk.hide(last)?;
+        }
+        for oid in revwalk {
+            let commit = self.repository().find_commit(oid?)?;
+            let time = time_source.time(&commit);
+            export.commits += transaction.execute(
+                "INSERT OR REPLACE INTO commits (oid, time, offset_minutes, message)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    commit.id().to_string(),
+                    time.seconds(),
+                    time.offset_minutes(),
+                    commit.message()
+                ],
+            )?;
+        }
+
+        let options = Options {
+            include_initial: true,
+            full_history: true,
+            from: last,
+            to: Some(tip),
+            time_source,
+            availability: true,
+            ..Options::default()
+        };
+        let mut events = match last {
+            Some(_) => self.resume(&options, load_state(&transaction)?)?,
+            None => self.events(&options)?,
+        };
+        let mut upsert = transaction.prepare(
+            "INSERT INTO videos (id, title, channel) VALUES (?1, ?2, ?3)
+             ON CONFLICT (id) DO UPDATE SET
+                 title = coalesce(excluded.title, title),
+                 channel = coalesce(excluded.channel, channel)",
+        )?;
+        for event in events.by_ref() {
+            let event = event?;
+            upsert.execute(params![
+                event.video_id,
+                event.info.title,
+                event.info.channel
+            ])?;
+            export.events += transaction.execute(
+                "INSERT OR IGNORE INTO events (commit_oid, video_id, kind) VALUES (?1, ?2, ?3)",
+                params![
+                    event.commit.to_string(),
+                    event.video_id,
+                    event.kind.as_str()
+                ],
+            )?;
+        }
+
+        // Song files renamed in place produce no event, so take the details
+        // of the videos still there from the tip, or from their last
+        // available version for the ones that turned private or deleted.
+        let mut last_known: HashMap<String, VideoInfo> = self
+            .unavailable_videos(tip, time_source)?
+            .into_iter()
+            .map(|video| (video.video_id, video.last_known))
+            .collect();
+        for entry in self.snapshot(tip, time_source)?.videos {
+            let info = last_known.remove(&entry.video_id).unwrap_or(entry.info);
+            upsert.execute(params![entry.video_id, info.title, info.channel])?;
+        }
+        drop(upsert);
+
+        transaction.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('last_commit', ?1)",
+            params![events.tip().to_string()],
+        )?;
+        transaction.commit()?;
+        Ok(export)
+    }
+}
+
+/// Rebuilds what the walk of the exported commits knew from the database.
+fn load_state(transaction: &Transaction) -> Result<State> {
+    let mut state = State::default();
+    let mut added =
+        transaction.prepare("SELECT DISTINCT video_id FROM events WHERE kind = 'added'")?;
+    for video in added.query_map([], |row| row.get(0))? {
+        state.already_added.insert(video?);
+    }
+    let mut removed = transaction.prepare(
+        "SELECT video_id, time, offset_minutes, max(time) FROM events
+         JOIN commits ON commits.oid = events.commit_oid
+         WHERE kind = 'removed' GROUP BY video_id",
+    )?;
+    let rows = removed.query_map([], |row| {
+        Ok((row.get(0)?, Time::new(row.get(1)?, row.get(2)?)))
+    })?;
+    for row in rows {
+        let (video, time) = row?;
+        state.removed_at.insert(video, time);
+    }
+    Ok(state)
+}
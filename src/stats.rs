@@ -0,0 +1,140 @@
+use std::collections::{BTreeMap, HashMap};
+
+use git2::Time;
+
+use crate::{
+    read_ids, DateFormat, EventKind, History, Options, Result, TimeSource, Timezone, VideoInfo,
+};
+
+/// How many videos to list as the busiest days and longest survivors.
+const TOP: usize = 10;
+
+/// Totals over the whole history of a playlist.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    /// Adds, including the initial import and re-adds.
+    pub added: usize,
+    pub re_added: usize,
+    pub removed: usize,
+    /// Videos in the playlist at HEAD.
+    pub current_size: usize,
+    pub per_year: Vec<Growth>,
+    pub per_month: Vec<Growth>,
+    /// Days with the most adds and removals, busiest first.
+    pub busiest_days: Vec<(String, usize)>,
+    /// Mean seconds between a video being added and removed again.
+    pub average_lifespan: Option<i64>,
+    /// Videos in the playlist today, longest there first.
+    pub longest_surviving: Vec<Survivor>,
+}
+
+/// Adds and removals within a year or month.
+#[derive(Clone, Debug, Default)]
+pub struct Growth {
+    pub period: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl Growth {
+    pub fn net(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
+}
+
+/// A current video and when it was last added.
+#[derive(Clone, Debug)]
+pub struct Survivor {
+    pub video_id: String,
+    pub info: VideoInfo,
+    pub since: Time,
+    pub seconds: i64,
+}
+
+impl History {
+    /// Walks the whole history of HEAD, grouping by days, months and years
+    /// in `timezone`. Survival times are counted up to `now`.
+    pub fn stats(&self, time_source: TimeSource, timezone: Timezone, now: i64) -> Result<Stats> {
+        let options = Options {
+            include_initial: true,
+            full_history: true,
+            time_source,
+            ..Options::default()
+        };
+        let period = |pattern: &str, time: &Time| {
+            DateFormat::Custom(pattern.to_string()).format(time, timezone)
+        };
+
+        let mut stats = Stats::default();
+        let mut years: BTreeMap<String, Growth> = BTreeMap::new();
+        let mut months: BTreeMap<String, Growth> = BTreeMap::new();
+        let mut days: HashMap<String, usize> = HashMap::new();
+        let mut added_at: HashMap<String, (Time, VideoInfo)> = HashMap::new();
+        let mut lifespans: Vec<i64> = Vec::new();
+
+        for event in self.events(&options)? {
+            let event = event?;
+            let added = match event.kind {
+                EventKind::Added | EventKind::ReAdded => true,
+                EventKind::Removed => false,
+                _ => continue,
+            };
+            let year = years.entry(period("%Y", &event.time)).or_default();
+            let month = months.entry(period("%Y-%m", &event.time)).or_default();
+            *days.entry(period("%Y-%m-%d", &event.time)).or_default() += 1;
+            if added {
+                stats.added += 1;
+                stats.re_added += (event.kind == EventKind::ReAdded) as usize;
+                year.added += 1;
+                month.added += 1;
+                added_at.insert(event.video_id, (event.time, event.info));
+            } else {
+                stats.removed += 1;
+                year.removed += 1;
+                month.removed += 1;
+                if let Some((since, _)) = added_at.remove(&event.video_id) {
+                    lifespans.push(event.time.seconds() - since.seconds());
+                }
+            }
+        }
+
+        let finish = |periods: BTreeMap<String, Growth>| -> Vec<Growth> {
+            periods
+                .into_iter()
+                .map(|(period, growth)| Growth { period, ..growth })
+                .collect()
+        };
+        stats.per_year = finish(years);
+        stats.per_month = finish(months);
+
+        let mut days: Vec<(String, usize)> = days.into_iter().collect();
+        days.sort_by(|(a_day, a), (b_day, b)| b.cmp(a).then(a_day.cmp(b_day)));
+        days.truncate(TOP);
+        stats.busiest_days = days;
+
+        if !lifespans.is_empty() {
+            stats.average_lifespan = Some(lifespans.iter().sum::<i64>() / lifespans.len() as i64);
+        }
+
+        let head = self.repository().head()?.peel_to_commit()?;
+        let current = read_ids(self.repository(), &head, self.layout())?;
+        stats.current_size = current.len();
+        let mut survivors: Vec<Survivor> = current
+            .into_iter()
+            .filter_map(|video| {
+                let (since, info) = added_at.remove(&video)?;
+                Some(Survivor {
+                    video_id: video,
+                    info,
+                    since,
+                    seconds: now - since.seconds(),
+                })
+            })
+            .collect();
+        survivors.sort_by(|a, b| b.seconds.cmp(&a.seconds).then(a.video_id.cmp(&b.video_id)));
+        survivors.truncate(TOP);
+        stats.longest_surviving = survivors;
+
+        Ok(stats)
+    }
+}
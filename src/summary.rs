@@ -0,0 +1,161 @@
+use std::{collections::HashSet, io::Read, path::Path};
+
+use git2::{Commit, ErrorCode, ObjectType, Repository};
+use serde_json::{Deserializer, Value};
+
+use crate::{Error, Layout, Result, Selector};
+
+/// Reads the ids of the videos currently in the playlist from HEAD's summary.
+pub fn get_current_ids(repo: &Repository) -> Result<HashSet<String>> {
+    read_ids(repo, &repo.head()?.peel_to_commit()?, &Layout::default())
+}
+
+/// Reads the ids of the videos in the playlist from a commit's summary, using
+/// the layout's id selector or detecting one if it has none.
+pub fn read_ids(repo: &Repository, commit: &Commit, layout: &Layout) -> Result<HashSet<String>> {
+    Ok(read_playlist(repo, commit, layout)?.into_iter().collect())
+}
+
+/// Like [`read_ids`], but keeps the order of the playlist.
+pub fn read_playlist(repo: &Repository, commit: &Commit, layout: &Layout) -> Result<Vec<String>> {
+    let summary = read_summary(repo, commit, &layout.summary_path)?;
+    let selector = match &layout.id_selector {
+        Some(selector) => selector.clone(),
+        None => detect_selector(&summary, &song_ids(repo, commit, layout)?),
+    };
+    select_ids(&summary, &selector)
+}
+
+/// Picks the candidate selector whose ids match the most song files, so that
+/// playlist item ids are not mistaken for video ids.
+pub fn detect_selector(summary: &[Value], song_ids: &HashSet<String>) -> Selector {
+    let candidates: Vec<Selector> = Selector::CANDIDATES
+        .iter()
+        .map(|candidate| candidate.parse().unwrap())
+        .collect();
+    let ids = |selector: &Selector| select_ids(summary, selector).unwrap_or_default();
+    let best = candidates
+        .iter()
+        .map(|selector| {
+            let matches = ids(selector)
+                .iter()
+                .filter(|id| song_ids.contains(*id))
+                .count();
+            (matches, selector)
+        })
+        .rev()
+        .max_by_key(|(matches, _)| *matches);
+    match best {
+        Some((matches, selector)) if matches > 0 => selector.clone(),
+        // Nothing to compare with, so go with the first selector finding ids.
+        _ => candidates
+            .iter()
+            .find(|selector| !ids(selector).is_empty())
+            .unwrap_or(&candidates[0])
+            .clone(),
+    }
+}
+
+/// Parses a summary file, which holds one JSON document per API response.
+pub fn read_summary(repo: &Repository, commit: &Commit, path: &Path) -> Result<Vec<Value>> {
+    let entry = match commit.tree()?.get_path(path) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            return Err(Error::SummaryInvalid(format!(
+                "{} not found in commit {}",
+                path.display(),
+                commit.id()
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let obj = entry.to_object(repo)?.peel_to_blob()?;
+    let mut file_content = String::new();
+    obj.content()
+        .read_to_string(&mut file_content)
+        .map_err(|e| Error::SummaryInvalid(e.to_string()))?;
+
+    Deserializer::from_str(&file_content)
+        .into_iter::<Value>()
+        .map(|value| value.map_err(|e| Error::SummaryInvalid(e.to_string())))
+        .collect()
+}
+
+fn select_ids(summary: &[Value], selector: &Selector) -> Result<Vec<String>> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut ids: Vec<String> = Vec::new();
+    for value in summary.iter().flat_map(|obj| selector.select(obj)) {
+        let id = value.as_str().ok_or_else(|| {
+            Error::SummaryInvalid(format!("{} is not a string: {}", selector, value))
+        })?;
+        if seen.insert(id) {
+            ids.push(id.to_string());
+        }
+    }
+    Ok(ids)
+}
+
+/// The ids of the song files in a commit.
+pub fn song_ids(repo: &Repository, commit: &Commit, layout: &Layout) -> Result<HashSet<String>> {
+    let tree = match commit.tree()?.get_path(&layout.songs_dir) {
+        Ok(entry) if entry.kind() == Some(ObjectType::Tree) => {
+            entry.to_object(repo)?.peel_to_tree()?
+        }
+        Ok(_) => return Ok(HashSet::new()),
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(tree
+        .iter()
+        .filter_map(|entry| {
+            let path = layout.songs_dir.join(entry.name()?);
+            layout.video_id(&path)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn summary() -> Vec<Value> {
+        vec![json!({
+            "items": [
+                {
+                    "id": "UExpdGVtMQ",
+                    "snippet": { "resourceId": { "videoId": "aaaaaaaaaaa" } },
+                },
+                {
+                    "id": "UExpdGVtMg",
+                    "snippet": { "resourceId": { "videoId": "bbbbbbbbbbb" } },
+                },
+            ]
+        })]
+    }
+
+    fn ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn detects_the_selector_matching_the_song_files() {
+        let selector = detect_selector(&summary(), &ids(&["aaaaaaaaaaa", "bbbbbbbbbbb"]));
+        assert_eq!(selector.to_string(), "items[].snippet.resourceId.videoId");
+    }
+
+    #[test]
+    fn picks_item_ids_when_they_are_what_matches() {
+        let selector = detect_selector(&summary(), &ids(&["UExpdGVtMQ"]));
+        assert_eq!(selector.to_string(), "items[].id");
+    }
+
+    #[test]
+    fn falls_back_to_the_first_selector_finding_ids() {
+        let selector = detect_selector(&summary(), &HashSet::new());
+        assert_eq!(selector.to_string(), "items[].snippet.resourceId.videoId");
+        let selector = detect_selector(&[json!({})], &HashSet::new());
+        assert_eq!(selector.to_string(), Selector::CANDIDATES[0]);
+    }
+}
@@ -0,0 +1,111 @@
+use git2::{Delta, DiffOptions, Oid};
+
+use crate::{EventKind, History, HistoryEvent, Result, TimeSource, VideoInfo};
+
+/// Every change to one video's song file, oldest first.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub video_id: String,
+    /// [`EventKind::Added`], [`EventKind::Modified`] and
+    /// [`EventKind::Removed`] events, each with the info recorded at that
+    /// point.
+    pub events: Vec<HistoryEvent>,
+}
+
+impl Timeline {
+    /// Whether the video is in the playlist after the last event.
+    pub fn is_present(&self) -> bool {
+        self.events
+            .last()
+            .is_some_and(|event| event.kind != EventKind::Removed)
+    }
+
+    /// Total seconds the video spent in the playlist, counting up to `now`
+    /// if it is still there.
+    pub fn seconds_in_playlist(&self, now: i64) -> i64 {
+        let mut total = 0;
+        let mut added_at: Option<i64> = None;
+        for event in &self.events {
+            match event.kind {
+                EventKind::Added => added_at = added_at.or(Some(event.time.seconds())),
+                EventKind::Removed => {
+                    if let Some(added_at) = added_at.take() {
+                        total += event.time.seconds() - added_at;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(added_at) = added_at {
+            total += now - added_at;
+        }
+        total
+    }
+}
+
+impl History {
+    /// Walks HEAD's history for the commits that touched `video`'s file.
+    pub fn timeline(&self, video: &str, time_source: TimeSource) -> Result<Timeline> {
+        let repo = self.repository();
+        let path = self.layout().song_path(video);
+
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        let mut commits = revwalk.collect::<std::result::Result<Vec<Oid>, _>>()?;
+        commits.reverse();
+
+        let mut events: Vec<HistoryEvent> = Vec::new();
+        for oid in commits {
+            let commit = repo.find_commit(oid)?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let mut diff_options = DiffOptions::new();
+            diff_options.pathspec(&path).disable_pathspec_match(true);
+            let diff = repo.diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&commit.tree()?),
+                Some(&mut diff_options),
+            )?;
+
+            for delta in diff.deltas() {
+                let (kind, blob) = match delta.status() {
+                    Delta::Added => (EventKind::Added, delta.new_file().id()),
+                    Delta::Modified => (EventKind::Modified, delta.new_file().id()),
+                    Delta::Deleted => (EventKind::Removed, delta.old_file().id()),
+                    _ => continue,
+                };
+                events.push(HistoryEvent {
+                    kind,
+                    video_id: video.to_string(),
+                    commit: oid,
+                    time: time_source.time(&commit),
+                    initial: parent_tree.is_none(),
+                    removed_at: None,
+                    info: VideoInfo::from_blob(repo, blob),
+                    changes: Vec::new(),
+                    availability: None,
+                });
+            }
+        }
+
+        Ok(Timeline {
+            video_id: video.to_string(),
+            events,
+        })
+    }
+}
+
+/// Formats a number of seconds as a rough duration such as `3 days`.
+pub fn format_span(seconds: i64) -> String {
+    let (count, unit) = match seconds {
+        s if s < 60 * 60 => (s / 60, "minute"),
+        s if s < 24 * 60 * 60 => (s / (60 * 60), "hour"),
+        s => (s / (24 * 60 * 60), "day"),
+    };
+    match count {
+        1 => format!("1 {}", unit),
+        _ => format!("{} {}s", count, unit),
+    }
+}
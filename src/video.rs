@@ -0,0 +1,125 @@
+use git2::{Oid, Repository};
+use serde_json::Value;
+
+/// Details about a video, read from its file in the songs directory.
+///
+/// Both YouTube Data API resources and yt-dlp style info files are
+/// understood; fields that cannot be found are left empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VideoInfo {
+    pub title: Option<String>,
+    pub channel: Option<String>,
+    /// Length in seconds.
+    pub duration: Option<u64>,
+}
+
+impl VideoInfo {
+    pub fn from_json(json: &Value) -> VideoInfo {
+        let string = |pointers: &[&str]| {
+            pointers
+                .iter()
+                .find_map(|pointer| json.pointer(pointer)?.as_str())
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let duration = match json.pointer("/contentDetails/duration") {
+            Some(Value::String(iso)) => parse_iso_duration(iso),
+            _ => json
+                .get("duration")
+                .and_then(Value::as_f64)
+                .map(|seconds| seconds as u64),
+        };
+        VideoInfo {
+            title: string(&["/snippet/title", "/title"]),
+            channel: string(&[
+                "/snippet/videoOwnerChannelTitle",
+                "/snippet/channelTitle",
+                "/channel",
+                "/uploader",
+            ]),
+            duration,
+        }
+    }
+
+    /// Reads the info from a song file blob, returning empty info if the
+    /// blob is missing or not JSON.
+    pub fn from_blob(repo: &Repository, blob: Oid) -> VideoInfo {
+        read_json(repo, blob)
+            .map(|json| VideoInfo::from_json(&json))
+            .unwrap_or_default()
+    }
+}
+
+/// Reads a blob as JSON, if it exists and is valid.
+pub(crate) fn read_json(repo: &Repository, blob: Oid) -> Option<Value> {
+    let blob = repo.find_blob(blob).ok()?;
+    serde_json::from_slice(blob.content()).ok()
+}
+
+/// Parses an ISO 8601 duration such as `PT3M32S` into seconds.
+fn parse_iso_duration(s: &str) -> Option<u64> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut units = 0;
+    for c in s.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            _ => {
+                let n: u64 = number.parse().ok()?;
+                number.clear();
+                units += 1;
+                seconds += n * match (c, in_time) {
+                    ('W', false) => 7 * 24 * 60 * 60,
+                    ('D', false) => 24 * 60 * 60,
+                    ('H', true) => 60 * 60,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    // Nothing at all, or a trailing number without a unit.
+    if units == 0 || !number.is_empty() {
+        return None;
+    }
+    Some(seconds)
+}
+
+/// Formats a length in seconds as `m:ss` or `h:mm:ss`.
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso_durations() {
+        assert_eq!(parse_iso_duration("PT3M32S"), Some(212));
+        assert_eq!(parse_iso_duration("P1DT2H"), Some(93_600));
+        assert_eq!(parse_iso_duration("P0D"), Some(0));
+        assert_eq!(parse_iso_duration("P1W"), Some(604_800));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for s in ["", "3M32S", "P", "PT", "P3M", "PT1D", "PTM", "PT3", "PT3X"] {
+            assert_eq!(parse_iso_duration(s), None, "{:?} parsed", s);
+        }
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(212), "3:32");
+        assert_eq!(format_duration(3_725), "1:02:05");
+    }
+}